@@ -49,6 +49,7 @@ let
     postBuild = ''
       wrapProgram $out/bin/kybe-backend \
         --set KYBE_BACKEND_STATIC_DIR ${../static} \
+        --set KYBE_BACKEND_ASSETS_DIR ${../assets} \
         --set KYBE_BACKEND_GIT_SHA ${self.rev or self.dirtyRev}
     '';
 
@@ -0,0 +1,278 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Mutex, mpsc},
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::{
+    alert::sinks::Sink,
+    config::types::{AlertsConfig, Config},
+};
+
+mod sinks;
+
+/// Sinks get this long to deliver an alert
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+static ALERTS: OnceLock<mpsc::UnboundedSender<Alert>> = OnceLock::new();
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Error,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Alert {
+    pub severity: Severity,
+    pub title: String,
+    pub message: String,
+    pub time: DateTime<Utc>,
+}
+
+/// Logs the alert and hands it to the sinks (once `Dispatcher::new` ran)
+pub fn notify(severity: Severity, title: impl Into<String>, message: impl Into<String>) {
+    let alert = Alert {
+        severity,
+        title: title.into(),
+        message: message.into(),
+        time: Utc::now(),
+    };
+
+    match severity {
+        Severity::Info => info!("{}: {}", alert.title, alert.message),
+        Severity::Warning => warn!("{}: {}", alert.title, alert.message),
+        Severity::Error | Severity::Critical => error!("{}: {}", alert.title, alert.message),
+    }
+
+    if let Some(alerts) = ALERTS.get() {
+        let _ = alerts.send(alert);
+    }
+}
+
+/// Decides which alerts a sink gets, the rest are counted for the next summary
+struct Gate {
+    min_severity: Severity,
+    // (severity, title) -> last time it was sent
+    sent: HashMap<(Severity, String), Instant>,
+    // send times within the last RATE_WINDOW
+    recent: VecDeque<Instant>,
+    // title -> (count, highest severity) since the last summary
+    suppressed: BTreeMap<String, (u32, Severity)>,
+}
+
+impl Gate {
+    fn allow(&mut self, alert: &Alert, config: &AlertsConfig) -> bool {
+        if alert.severity < self.min_severity {
+            return false;
+        }
+
+        let now = Instant::now();
+        let dedup = Duration::from_secs(config.dedup_secs);
+        let key = (alert.severity, alert.title.clone());
+
+        while self
+            .recent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > RATE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+
+        let duplicate = self
+            .sent
+            .get(&key)
+            .is_some_and(|t| now.duration_since(*t) < dedup);
+        let limited = self.recent.len() >= config.rate_limit as usize;
+
+        if duplicate || limited {
+            let entry = self
+                .suppressed
+                .entry(alert.title.clone())
+                .or_insert((0, alert.severity));
+            entry.0 += 1;
+            entry.1 = entry.1.max(alert.severity);
+            return false;
+        }
+
+        self.sent.insert(key, now);
+        self.recent.push_back(now);
+        true
+    }
+
+    /// A single alert listing everything suppressed since the last summary
+    fn summary(&mut self, config: &AlertsConfig) -> Option<Alert> {
+        let dedup = Duration::from_secs(config.dedup_secs);
+        self.sent.retain(|_, t| t.elapsed() < dedup);
+
+        if self.suppressed.is_empty() {
+            return None;
+        }
+
+        let suppressed = std::mem::take(&mut self.suppressed);
+        let total: u32 = suppressed.values().map(|(count, _)| count).sum();
+        let severity = suppressed
+            .values()
+            .map(|(_, severity)| *severity)
+            .max()
+            .unwrap_or_default();
+
+        let lines = suppressed
+            .iter()
+            .map(|(title, (count, _))| format!("- {title}: {count}x"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Some(Alert {
+            severity,
+            title: "Alert summary".into(),
+            message: format!(
+                "{total} alerts suppressed in the last {} min:\n{lines}",
+                config.summary_secs / 60
+            ),
+            time: Utc::now(),
+        })
+    }
+}
+
+/// Sends queued alerts to the sinks, runs as a supervised subsystem
+pub struct Dispatcher {
+    config: AlertsConfig,
+    sinks: Vec<(Arc<Sink>, std::sync::Mutex<Gate>)>,
+    alerts: Mutex<mpsc::UnboundedReceiver<Alert>>,
+}
+
+impl Dispatcher {
+    /// Builds the sinks and starts queueing alerts, the config is only read at startup
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let sinks = config
+            .alerts
+            .sinks
+            .iter()
+            .map(|sink| {
+                let gate = Gate {
+                    min_severity: sink.min_severity,
+                    sent: HashMap::new(),
+                    recent: VecDeque::new(),
+                    suppressed: BTreeMap::new(),
+                };
+                Ok((
+                    Arc::new(Sink::new(&sink.kind, config)?),
+                    std::sync::Mutex::new(gate),
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        if ALERTS.set(tx).is_err() {
+            anyhow::bail!("alert dispatcher already initialized");
+        }
+
+        info!("alerts: {} sinks configured", sinks.len());
+
+        Ok(Self {
+            config: config.alerts.clone(),
+            sinks,
+            alerts: Mutex::new(rx),
+        })
+    }
+
+    /// Delivers alerts until `shutdown` is cancelled, then flushes the queue and summaries
+    pub async fn run(self: Arc<Self>, shutdown: CancellationToken) -> anyhow::Result<()> {
+        let mut alerts = self.alerts.lock().await;
+        let mut sending = JoinSet::new();
+        let mut summary =
+            tokio::time::interval(Duration::from_secs(self.config.summary_secs.max(60)));
+        summary.tick().await;
+
+        loop {
+            tokio::select! {
+                Some(alert) = alerts.recv() => self.dispatch(&alert, &mut sending),
+                _ = summary.tick() => self.summarize(&mut sending),
+                Some(_) = sending.join_next() => {}
+                () = shutdown.cancelled() => break,
+            }
+        }
+
+        // alerts about the shutdown itself are still in the queue
+        while let Ok(alert) = alerts.try_recv() {
+            self.dispatch(&alert, &mut sending);
+        }
+        self.summarize(&mut sending);
+
+        let drain = async { while sending.join_next().await.is_some() {} };
+        if tokio::time::timeout(SEND_TIMEOUT, drain).await.is_err() {
+            warn!("alerts: some alerts weren't delivered before shutdown");
+        }
+
+        Ok(())
+    }
+
+    fn dispatch(&self, alert: &Alert, sending: &mut JoinSet<()>) {
+        for (sink, gate) in &self.sinks {
+            let allowed = gate
+                .lock()
+                .map(|mut g| g.allow(alert, &self.config))
+                .unwrap_or(true);
+
+            if allowed {
+                send(sending, Arc::clone(sink), alert.clone());
+            }
+        }
+    }
+
+    fn summarize(&self, sending: &mut JoinSet<()>) {
+        for (sink, gate) in &self.sinks {
+            if let Some(alert) = gate.lock().ok().and_then(|mut g| g.summary(&self.config)) {
+                send(sending, Arc::clone(sink), alert);
+            }
+        }
+    }
+}
+
+fn send(sending: &mut JoinSet<()>, sink: Arc<Sink>, alert: Alert) {
+    sending.spawn(async move {
+        match tokio::time::timeout(SEND_TIMEOUT, sink.send(&alert)).await {
+            Ok(Ok(())) => {}
+            // only logged, alerting about failed alerts would loop
+            Ok(Err(e)) => warn!(
+                "alerts: {} failed to send {:?}: {e:#}",
+                sink.name(),
+                alert.title
+            ),
+            Err(_) => warn!(
+                "alerts: {} timed out sending {:?}",
+                sink.name(),
+                alert.title
+            ),
+        }
+    });
+}
@@ -0,0 +1,224 @@
+use std::{process::Stdio, time::Duration};
+
+use anyhow::{Context, anyhow};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{io::AsyncWriteExt, process::Command, sync::OnceCell};
+
+use crate::{
+    GIT_SHA,
+    alert::{Alert, Severity},
+    config::types::{AlertSinkKind, Config},
+};
+
+const DISCORD_API: &str = "https://discord.com/api/v10";
+/// Discord embed descriptions are limited to 4096 characters
+const DISCORD_MAX_LENGTH: usize = 4000;
+
+pub enum Sink {
+    Webhook {
+        client: Client,
+        url: String,
+    },
+    DiscordWebhook {
+        client: Client,
+        url: String,
+    },
+    DiscordDm {
+        client: Client,
+        token: String,
+        admin_id: String,
+        // the DM channel, created on the first alert
+        channel: OnceCell<String>,
+    },
+    Ntfy {
+        client: Client,
+        url: String,
+        token: Option<String>,
+    },
+    Email {
+        to: String,
+        from: String,
+        command: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct DiscordChannel {
+    id: String,
+}
+
+impl Sink {
+    pub fn new(kind: &AlertSinkKind, config: &Config) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .user_agent("2kybe3 / kybe-backend")
+            .timeout(Duration::from_secs(10))
+            .build()?;
+
+        Ok(match kind {
+            AlertSinkKind::Webhook { url } => Self::Webhook {
+                client,
+                url: url.clone(),
+            },
+            AlertSinkKind::DiscordWebhook { url } => Self::DiscordWebhook {
+                client,
+                url: url.clone(),
+            },
+            AlertSinkKind::DiscordDm => Self::DiscordDm {
+                client,
+                token: config.discord_bot.token.clone(),
+                admin_id: config.discord_bot.admin_id.clone(),
+                channel: OnceCell::new(),
+            },
+            AlertSinkKind::Ntfy { url, token } => Self::Ntfy {
+                client,
+                url: url.clone(),
+                token: token.clone().filter(|t| !t.is_empty()),
+            },
+            AlertSinkKind::Email { to, from, command } => Self::Email {
+                to: to.clone(),
+                from: from.clone(),
+                command: command.clone(),
+            },
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Webhook { .. } => "webhook",
+            Self::DiscordWebhook { .. } => "discord_webhook",
+            Self::DiscordDm { .. } => "discord_dm",
+            Self::Ntfy { .. } => "ntfy",
+            Self::Email { .. } => "email",
+        }
+    }
+
+    pub async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        match self {
+            Self::Webhook { client, url } => {
+                client
+                    .post(url)
+                    .json(&json!({
+                        "service": "kybe-backend",
+                        "version": GIT_SHA.as_str(),
+                        "severity": alert.severity,
+                        "title": alert.title,
+                        "message": alert.message,
+                        "time": alert.time,
+                    }))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            Self::DiscordWebhook { client, url } => {
+                client
+                    .post(url)
+                    .json(&discord_message(alert))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            Self::DiscordDm {
+                client,
+                token,
+                admin_id,
+                channel,
+            } => {
+                let channel = channel
+                    .get_or_try_init(|| async {
+                        let channel: DiscordChannel = client
+                            .post(format!("{DISCORD_API}/users/@me/channels"))
+                            .header("Authorization", format!("Bot {token}"))
+                            .json(&json!({ "recipient_id": admin_id }))
+                            .send()
+                            .await?
+                            .error_for_status()?
+                            .json()
+                            .await?;
+                        anyhow::Ok(channel.id)
+                    })
+                    .await
+                    .context("failed to open DM channel")?;
+
+                client
+                    .post(format!("{DISCORD_API}/channels/{channel}/messages"))
+                    .header("Authorization", format!("Bot {token}"))
+                    .json(&discord_message(alert))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            Self::Ntfy { client, url, token } => {
+                let priority = match alert.severity {
+                    Severity::Info => "low",
+                    Severity::Warning => "default",
+                    Severity::Error => "high",
+                    Severity::Critical => "urgent",
+                };
+
+                let mut request = client
+                    .post(url)
+                    .header("Title", format!("kybe-backend: {}", alert.title))
+                    .header("Priority", priority)
+                    .header("Tags", alert.severity.to_string())
+                    .body(alert.message.clone());
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+                request.send().await?.error_for_status()?;
+            }
+            Self::Email { to, from, command } => {
+                let mail = format!(
+                    "From: {from}\r\nTo: {to}\r\nSubject: [kybe-backend] {}: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n\r\n-- \r\nkybe-backend {}\r\n",
+                    alert.severity,
+                    // a line break would end the header
+                    alert.title.replace(['\r', '\n'], " "),
+                    alert.time.to_rfc2822(),
+                    alert.message,
+                    GIT_SHA.as_str(),
+                );
+
+                let mut child = Command::new(command)
+                    .args(["-t", "-i"])
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .with_context(|| format!("failed to run {command}"))?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(mail.as_bytes()).await?;
+                }
+
+                let status = child.wait().await?;
+                if !status.success() {
+                    return Err(anyhow!("{command} exited with {status}"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn discord_message(alert: &Alert) -> serde_json::Value {
+    let color = match alert.severity {
+        Severity::Info => 0x3498db,
+        Severity::Warning => 0xf1c40f,
+        Severity::Error => 0xe74c3c,
+        Severity::Critical => 0x8e0000,
+    };
+    let description = match alert.message.char_indices().nth(DISCORD_MAX_LENGTH) {
+        Some((end, _)) => format!("{}…", &alert.message[..end]),
+        None => alert.message.clone(),
+    };
+
+    json!({
+        "embeds": [{
+            "title": format!("{} ({})", alert.title, alert.severity),
+            "description": description,
+            "color": color,
+            "timestamp": alert.time.to_rfc3339(),
+            "footer": { "text": format!("kybe-backend {}", GIT_SHA.as_str()) },
+        }]
+    })
+}
@@ -0,0 +1,300 @@
+use std::{io, net::IpAddr, path::Path, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::DateTime;
+
+use crate::{
+    commands::{Arg, Command, Invocation, Role, text},
+    config::{SharedConfig, reload},
+    external::lastfm::LastFM,
+    honeypot,
+    maxmind::MaxMind,
+    webserver::render::{
+        RenderFormat, Style,
+        builders::TextBlobBuilder,
+        color::bit4::Bit4Color,
+        object::{Object, Objects},
+    },
+};
+
+/// Lines `logs` shows without an argument
+const DEFAULT_LINES: usize = 50;
+/// Sessions `sessions` lists, newest first
+const MAX_SESSIONS: usize = 20;
+
+/// Loads config/config.toml again, same as changing the file
+pub struct Reload(pub SharedConfig);
+
+#[async_trait]
+impl Command for Reload {
+    fn id(&self) -> &'static str {
+        "reload"
+    }
+
+    fn help(&self) -> &str {
+        "Reloads config/config.toml"
+    }
+
+    fn role(&self) -> Option<Role> {
+        Some(Role::Admin)
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        let changed = match reload::reload(&self.0).await {
+            Ok(changed) => changed,
+            Err(e) => {
+                return red(format!("Reload failed, keeping the current config: {e}\n")).into();
+            }
+        };
+        if changed.is_empty() {
+            return text("Reloaded, nothing changed\n").into();
+        }
+
+        let mut objects = vec![red("Changed\n")];
+        for key in changed {
+            objects.push(text(format!("  {key}")));
+            if reload::restart_required(&key) {
+                objects.push(
+                    TextBlobBuilder::new(" (restart required)")
+                        .style(Style::new().fg(Bit4Color::YELLOW))
+                        .into(),
+                );
+            }
+            objects.push(text("\n"));
+        }
+        objects.into()
+    }
+}
+
+/// The last log lines, whatever logger.level lets through
+pub struct Logs;
+
+#[async_trait]
+impl Command for Logs {
+    fn id(&self) -> &'static str {
+        "logs"
+    }
+
+    fn help(&self) -> &str {
+        "The most recent log lines"
+    }
+
+    fn args(&self) -> &[Arg] {
+        const ARGS: &[Arg] = &[Arg::new("lines", "How many, 50 by default")];
+        ARGS
+    }
+
+    fn role(&self) -> Option<Role> {
+        Some(Role::Viewer)
+    }
+
+    fn format(&self) -> Option<RenderFormat> {
+        Some(RenderFormat::Plain)
+    }
+
+    async fn execute(&self, invocation: &Invocation<'_>) -> Objects {
+        let lines = match invocation.args.first() {
+            Some(lines) => match lines.parse() {
+                Ok(lines) => lines,
+                Err(_) => return text("lines has to be a number\n").into(),
+            },
+            None => DEFAULT_LINES,
+        };
+        let mut logs = crate::logger::recent(lines).join("\n");
+        logs.push('\n');
+        text(logs).into()
+    }
+}
+
+/// The sessions recorded by privacy.honeypot
+pub struct Sessions(pub SharedConfig);
+
+#[async_trait]
+impl Command for Sessions {
+    fn id(&self) -> &'static str {
+        "sessions"
+    }
+
+    fn help(&self) -> &str {
+        "Recorded honeypot sessions, or the events of one"
+    }
+
+    fn args(&self) -> &[Arg] {
+        const ARGS: &[Arg] = &[Arg::new("id", "A session from the list")];
+        ARGS
+    }
+
+    fn role(&self) -> Option<Role> {
+        Some(Role::Viewer)
+    }
+
+    fn format(&self) -> Option<RenderFormat> {
+        Some(RenderFormat::Plain)
+    }
+
+    async fn execute(&self, invocation: &Invocation<'_>) -> Objects {
+        let dir = self.0.load().privacy.honeypot.dir.clone();
+        if dir.is_empty() {
+            return text("The honeypot is disabled\n").into();
+        }
+        let dir = Path::new(&dir);
+
+        // the events as they are recorded, JSON lines
+        if let Some(id) = invocation.args.first() {
+            let Some(path) = honeypot::path(dir, id, "jsonl") else {
+                return text(format!("No session {id}\n")).into();
+            };
+            return match tokio::fs::read_to_string(&path).await {
+                Ok(events) => text(events).into(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    text(format!("No session {id}\n")).into()
+                }
+                Err(e) => text(format!("Failed to read the session: {e}\n")).into(),
+            };
+        }
+
+        let sessions = match honeypot::list(dir).await {
+            Ok(sessions) => sessions,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return text(format!("Failed to list the sessions: {e}\n")).into(),
+        };
+        if sessions.is_empty() {
+            return text("No sessions recorded yet\n").into();
+        }
+
+        let mut list = match sessions.len() > MAX_SESSIONS {
+            true => format!(
+                "{} sessions, the newest {MAX_SESSIONS}:\n\n",
+                sessions.len()
+            ),
+            false => format!("{} sessions:\n\n", sessions.len()),
+        };
+        for session in sessions.iter().take(MAX_SESSIONS) {
+            let started = session
+                .started
+                .map(|started| started.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "?".into());
+            let ip = session
+                .ip
+                .map(|ip| ip.to_string())
+                .unwrap_or_else(|| "?".into());
+            let cast = if session.cast { "  cast" } else { "" };
+            let connected = if session.ended.is_none() {
+                "  connected"
+            } else {
+                ""
+            };
+            list.push_str(&format!(
+                "{}  {started}  {ip}  users: {}  auths: {}  commands: {}{cast}{connected}\n",
+                session.id,
+                session.users.join(", "),
+                session.auth_attempts,
+                session.commands,
+            ));
+        }
+        text(list).into()
+    }
+}
+
+/// Which databases are loaded, or what they know about an IP
+pub struct MaxMindState(pub Arc<MaxMind>);
+
+#[async_trait]
+impl Command for MaxMindState {
+    fn id(&self) -> &'static str {
+        "maxmind"
+    }
+
+    fn help(&self) -> &str {
+        "The loaded MaxMind databases, or a lookup"
+    }
+
+    fn args(&self) -> &[Arg] {
+        const ARGS: &[Arg] = &[Arg::new("ip", "The address to look up")];
+        ARGS
+    }
+
+    fn role(&self) -> Option<Role> {
+        Some(Role::Viewer)
+    }
+
+    fn format(&self) -> Option<RenderFormat> {
+        Some(RenderFormat::Plain)
+    }
+
+    async fn execute(&self, invocation: &Invocation<'_>) -> Objects {
+        let Some(ip) = invocation.args.first() else {
+            let built = |epoch: Option<u64>| {
+                epoch
+                    .and_then(|epoch| DateTime::from_timestamp(epoch as i64, 0))
+                    .map(|built| format!("built {}", built.format("%Y-%m-%d")))
+                    .unwrap_or_else(|| "not loaded".into())
+            };
+            return text(format!(
+                "City: {}\nASN: {}\n",
+                built(self.0.city_build_epoch()),
+                built(self.0.asn_build_epoch())
+            ))
+            .into();
+        };
+
+        let Ok(ip) = ip.parse::<IpAddr>() else {
+            return text(format!("{ip} isn't an IP address\n")).into();
+        };
+        match self
+            .0
+            .lookup(ip)
+            .and_then(|response| Ok(serde_json::to_string_pretty(&response)?))
+        {
+            Ok(response) => text(format!("{response}\n")).into(),
+            Err(e) => text(format!("Lookup failed: {e}\n")).into(),
+        }
+    }
+}
+
+/// The cached now playing and when it was fetched
+pub struct LastFMState(pub Option<Arc<LastFM>>);
+
+#[async_trait]
+impl Command for LastFMState {
+    fn id(&self) -> &'static str {
+        "lastfm"
+    }
+
+    fn help(&self) -> &str {
+        "The cached LastFM now playing"
+    }
+
+    fn role(&self) -> Option<Role> {
+        Some(Role::Viewer)
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        let Some(lastfm) = &self.0 else {
+            return text("LastFM is disabled\n").into();
+        };
+        let cache = lastfm.get_playing().await;
+
+        let mut objects = vec![red("Playing\n")];
+        match cache.result {
+            Some(playing) => objects.push(text(format!(
+                "  {} - {}\n  {}\n",
+                playing.artist, playing.name, playing.url
+            ))),
+            None => objects.push(text("  nothing\n")),
+        }
+        objects.push(red("\nSynced\n"));
+        objects.push(text(format!(
+            "  {} ({}s ago)\n",
+            cache.sync.format("%Y-%m-%d %H:%M:%S"),
+            cache.sync_age / 1000
+        )));
+        objects.into()
+    }
+}
+
+fn red(text: impl Into<String>) -> Object {
+    TextBlobBuilder::new(text)
+        .style(Style::new().fg(Bit4Color::RED))
+        .into()
+}
@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+
+use crate::{
+    commands::{Command, Frontend, Invocation, text},
+    webserver::render::{RenderFormat, object::Objects},
+};
+
+const PGP_KEY: &str = include_str!("../../static/pgp.txt");
+const IDENTITY: &str = include_str!("../../static/ident.txt");
+
+pub struct Ident;
+
+#[async_trait]
+impl Command for Ident {
+    fn id(&self) -> &'static str {
+        "ident"
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["identity", "who"]
+    }
+
+    fn help(&self) -> &str {
+        "Who I am and where to find me"
+    }
+
+    fn available(&self, _: Frontend) -> bool {
+        true
+    }
+
+    fn format(&self) -> Option<RenderFormat> {
+        Some(RenderFormat::Plain)
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        text(IDENTITY).into()
+    }
+}
+
+pub struct Pgp;
+
+#[async_trait]
+impl Command for Pgp {
+    fn id(&self) -> &'static str {
+        "pgp"
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &["gpg"]
+    }
+
+    fn help(&self) -> &str {
+        "My PGP public key"
+    }
+
+    fn available(&self, _: Frontend) -> bool {
+        true
+    }
+
+    fn format(&self) -> Option<RenderFormat> {
+        Some(RenderFormat::Plain)
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        text(PGP_KEY).into()
+    }
+}
+
+pub struct Ping;
+
+#[async_trait]
+impl Command for Ping {
+    fn id(&self) -> &'static str {
+        "ping"
+    }
+
+    fn help(&self) -> &str {
+        "Answers with pong"
+    }
+
+    fn available(&self, _: Frontend) -> bool {
+        true
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        text("pong\n").into()
+    }
+}
+
+pub struct Version;
+
+#[async_trait]
+impl Command for Version {
+    fn id(&self) -> &'static str {
+        "version"
+    }
+
+    fn help(&self) -> &str {
+        "The commit this server was built from"
+    }
+
+    fn available(&self, _: Frontend) -> bool {
+        true
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        text(format!("{}\n", *crate::GIT_SHA)).into()
+    }
+}
+
+pub struct Clear;
+
+#[async_trait]
+impl Command for Clear {
+    fn id(&self) -> &'static str {
+        "clear"
+    }
+
+    fn help(&self) -> &str {
+        "Clears the screen"
+    }
+
+    fn available(&self, frontend: Frontend) -> bool {
+        frontend == Frontend::Shell
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        text("\x1b[2J\x1b[H").into()
+    }
+}
+
+pub struct Exit;
+
+#[async_trait]
+impl Command for Exit {
+    fn id(&self) -> &'static str {
+        "exit"
+    }
+
+    fn help(&self) -> &str {
+        "Closes the session"
+    }
+
+    fn available(&self, frontend: Frontend) -> bool {
+        frontend == Frontend::Shell
+    }
+
+    fn ends_session(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        Vec::new().into()
+    }
+}
@@ -0,0 +1,353 @@
+use std::{net::IpAddr, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{
+    config::SharedConfig,
+    maxmind::MaxMind,
+    webserver::render::{
+        Page, RenderFormat, RenderOptions, Style,
+        builders::TextBlobBuilder,
+        color::bit4::Bit4Color,
+        object::{Object, Objects},
+    },
+};
+
+mod admin;
+mod builtin;
+mod site;
+
+pub use site::Site;
+
+/// Where a command is run from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frontend {
+    // the interactive SSH shell
+    Shell,
+    // `ssh host <command>`
+    Exec,
+    // a Discord slash command
+    Discord,
+}
+
+impl Frontend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Frontend::Shell => "shell",
+            Frontend::Exec => "exec",
+            Frontend::Discord => "discord",
+        }
+    }
+}
+
+/// What an SSH key from ssh.authorized_keys may run, visitors without one have no role
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    // reads logs, sessions and state
+    Viewer,
+    // also changes things, like reloading the config
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// A positional argument of a command
+#[derive(Clone, Copy, Debug)]
+pub struct Arg {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub required: bool,
+    // takes all remaining words, only the last argument can
+    pub rest: bool,
+    // values offered by tab completion
+    pub values: Option<fn() -> Vec<String>>,
+}
+
+impl Arg {
+    pub const fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            required: false,
+            rest: false,
+            values: None,
+        }
+    }
+
+    pub const fn rest(self) -> Self {
+        Self { rest: true, ..self }
+    }
+
+    pub const fn values(self, values: fn() -> Vec<String>) -> Self {
+        Self {
+            values: Some(values),
+            ..self
+        }
+    }
+
+    /// `<name>`, `[name]` or `[name...]` as shown in the help
+    fn usage(&self) -> String {
+        let rest = if self.rest { "..." } else { "" };
+        match self.required {
+            true => format!("<{}{rest}>", self.name),
+            false => format!("[{}{rest}]", self.name),
+        }
+    }
+}
+
+/// Everything a command knows about its caller
+pub struct Invocation<'a> {
+    pub frontend: Frontend,
+    pub args: &'a [&'a str],
+    pub client: Option<IpAddr>,
+    // the role of the key the client logged in with
+    pub role: Option<Role>,
+    // the format is only a default, see Command::format
+    pub render: RenderOptions,
+}
+
+/// A command of the SSH shell, SSH exec and (where it makes sense) the Discord bot
+#[async_trait]
+pub trait Command: Send + Sync {
+    /// Identifies the command in metrics and traces, commands sharing an implementation
+    /// (the pages) share one id so the label set stays small
+    fn id(&self) -> &'static str;
+
+    fn name(&self) -> &str {
+        self.id()
+    }
+
+    fn aliases(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// One line shown in the help and as the slash command's description
+    fn help(&self) -> &str;
+
+    fn args(&self) -> &[Arg] {
+        &[]
+    }
+
+    fn available(&self, frontend: Frontend) -> bool {
+        frontend != Frontend::Discord
+    }
+
+    /// The role needed to run the command, everyone can by default
+    ///
+    /// Commands which need one are audited, Discord users never have a role.
+    fn role(&self) -> Option<Role> {
+        None
+    }
+
+    /// Overrides the frontend's format, plain output stays pipeable (`ssh … pgp | gpg --import`)
+    fn format(&self) -> Option<RenderFormat> {
+        None
+    }
+
+    /// The shell closes the session after running the command
+    fn ends_session(&self) -> bool {
+        false
+    }
+
+    /// Opens the full-screen browser when there is a pty, `execute` is the fallback without one
+    fn full_screen(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, invocation: &Invocation<'_>) -> Objects;
+}
+
+/// The commands, registered once for every frontend
+pub struct Registry {
+    config: SharedConfig,
+    site: Arc<Site>,
+    commands: Vec<Arc<dyn Command>>,
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Registry").finish_non_exhaustive()
+    }
+}
+
+impl Registry {
+    pub fn new(config: SharedConfig, site: Arc<Site>, mm: Arc<MaxMind>) -> Self {
+        let commands: Vec<Arc<dyn Command>> = vec![
+            Arc::new(site::Now(Arc::clone(&site))),
+            Arc::new(site::Canvas),
+            Arc::new(site::Browse(Arc::clone(&site))),
+            Arc::new(builtin::Ident),
+            Arc::new(builtin::Pgp),
+            Arc::new(builtin::Ping),
+            Arc::new(builtin::Version),
+            Arc::new(builtin::Clear),
+            Arc::new(builtin::Exit),
+            Arc::new(admin::Reload(Arc::clone(&config))),
+            Arc::new(admin::Logs),
+            Arc::new(admin::Sessions(Arc::clone(&config))),
+            Arc::new(admin::MaxMindState(mm)),
+            Arc::new(admin::LastFMState(site.lastfm.clone())),
+        ];
+        Self {
+            config,
+            site,
+            commands,
+        }
+    }
+
+    /// The commands `frontend` can run with `role`, pages first as they change with the content
+    pub fn commands(&self, frontend: Frontend, role: Option<Role>) -> Vec<Arc<dyn Command>> {
+        self.site
+            .commands()
+            .into_iter()
+            .chain(self.commands.iter().cloned())
+            .filter(|command| command.available(frontend) && command.role() <= role)
+            .collect()
+    }
+
+    /// The command called `name` or one of its aliases, `help` isn't a command
+    pub fn find(
+        &self,
+        name: &str,
+        frontend: Frontend,
+        role: Option<Role>,
+    ) -> Option<Arc<dyn Command>> {
+        self.commands(frontend, role)
+            .into_iter()
+            .find(|command| command.name() == name || command.aliases().contains(&name))
+    }
+
+    /// Metric and span label for `name`, `help` and anything unknown included
+    pub fn label(&self, name: &str, frontend: Frontend, role: Option<Role>) -> &'static str {
+        match (name, self.find(name, frontend, role)) {
+            (_, Some(command)) => command.id(),
+            ("help", None) => "help",
+            _ => "unknown",
+        }
+    }
+
+    /// What the full-screen browser shows, the pages and `now`
+    pub fn sections(&self) -> Vec<String> {
+        self.site.sections()
+    }
+
+    /// Names the frontend can run with `role`, for error messages
+    pub fn names(&self, frontend: Frontend, role: Option<Role>) -> Vec<String> {
+        self.commands(frontend, role)
+            .iter()
+            .map(|command| command.name().to_string())
+            .chain(["help".into()])
+            .collect()
+    }
+
+    /// Runs `name` and renders its output, None if there is no such command
+    ///
+    /// Missing arguments are answered with the command's usage.
+    pub async fn run(&self, name: &str, invocation: &Invocation<'_>) -> Option<String> {
+        if name == "help" {
+            let help = self.help(invocation.frontend, invocation.role);
+            return Some(self.render("help", None, help, invocation));
+        }
+
+        let command = self.find(name, invocation.frontend, invocation.role)?;
+        let required = command.args().iter().filter(|arg| arg.required).count();
+        let objects = if invocation.args.len() < required {
+            text(format!("Usage: {}\n", usage(command.as_ref()))).into()
+        } else {
+            command.execute(invocation).await
+        };
+        Some(self.render(command.name(), command.format(), objects, invocation))
+    }
+
+    fn render(
+        &self,
+        title: &str,
+        format: Option<RenderFormat>,
+        objects: Objects,
+        invocation: &Invocation<'_>,
+    ) -> String {
+        let config = self.config.load();
+        let mut options = invocation.render;
+        if let Some(format) = format {
+            options.format = format;
+        }
+        Page::new(title, &config, objects.into_iter().collect())
+            .render(options)
+            .into_data()
+    }
+
+    /// Lists the commands `frontend` can run with `role`, with their arguments and aliases
+    pub fn help(&self, frontend: Frontend, role: Option<Role>) -> Objects {
+        let commands = self.commands(frontend, role);
+        let usages = commands
+            .iter()
+            .map(|command| usage(command.as_ref()))
+            .chain(["help".into()])
+            .collect::<Vec<_>>();
+        let width = usages.iter().map(String::len).max().unwrap_or_default();
+
+        let mut objects = vec![
+            TextBlobBuilder::new("Commands\n\n")
+                .style(Style::new().fg(Bit4Color::RED))
+                .into(),
+        ];
+        let helps = commands
+            .iter()
+            .map(|command| {
+                let mut help = command.help().to_string();
+                if !command.aliases().is_empty() {
+                    help.push_str(&format!(" (also {})", command.aliases().join(", ")));
+                }
+                for arg in command.args() {
+                    help.push_str(&format!("\n  {:width$}    {}: {}", "", arg.name, arg.help));
+                }
+                help
+            })
+            .chain(["Shows this list".into()]);
+        for (usage, help) in usages.iter().zip(helps) {
+            objects.push(
+                TextBlobBuilder::new(format!("  {usage:width$}  "))
+                    .style(Style::new().fg(Bit4Color::YELLOW))
+                    .into(),
+            );
+            objects.push(text(format!("{help}\n")));
+        }
+        objects.into()
+    }
+
+    /// Candidates for the word after `words`, command names or the values of the argument
+    pub fn complete(&self, words: &[&str], frontend: Frontend, role: Option<Role>) -> Vec<String> {
+        let Some((name, args)) = words.split_first() else {
+            return self.names(frontend, role);
+        };
+        let Some(command) = self.find(name, frontend, role) else {
+            return Vec::new();
+        };
+        let arg = match command.args().get(args.len()) {
+            Some(arg) => Some(arg),
+            None => command.args().last().filter(|arg| arg.rest),
+        };
+        arg.and_then(|arg| arg.values)
+            .map(|values| values())
+            .unwrap_or_default()
+    }
+}
+
+/// `name <arg> [arg]`
+pub fn usage(command: &dyn Command) -> String {
+    std::iter::once(command.name().to_string())
+        .chain(command.args().iter().map(Arg::usage))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn text(text: impl Into<String>) -> Object {
+    TextBlobBuilder::new(text).into()
+}
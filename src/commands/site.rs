@@ -0,0 +1,243 @@
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{
+    commands::{Arg, Command, Frontend, Invocation},
+    config::SharedConfig,
+    external::lastfm::LastFM,
+    webserver::{
+        Ident, RequestContext, common,
+        content::{self, ContentPage, DynamicData, SharedPages, types::DynamicBlock},
+        render::{
+            Style,
+            builders::{COLOR_MAPPING, TextBlobBuilder},
+            color::bit4::Bit4Color,
+            object::Objects,
+        },
+    },
+};
+
+/// The website's pages as commands, rendered the way `curl` gets them
+pub struct Site {
+    pub config: SharedConfig,
+    pub pages: SharedPages,
+    pub lastfm: Option<Arc<LastFM>>,
+}
+
+impl fmt::Debug for Site {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Site").finish_non_exhaustive()
+    }
+}
+
+impl Site {
+    /// A command for every page, the root page is `home`
+    pub fn commands(&self) -> Vec<Arc<dyn Command>> {
+        let mut pages = self
+            .pages
+            .load()
+            .iter()
+            .filter_map(|page| Some((page_command(&page.route)?, Arc::clone(page))))
+            .collect::<Vec<_>>();
+        pages.sort_by_key(|(name, _)| name != "home");
+        pages
+            .into_iter()
+            .map(|(name, page)| {
+                Arc::new(PageCommand {
+                    name,
+                    page,
+                    lastfm: self.lastfm.clone(),
+                    hostname: self.config.load().ssh.hostname.clone(),
+                }) as Arc<dyn Command>
+            })
+            .collect()
+    }
+
+    /// The commands without arguments showing a part of the site
+    pub fn sections(&self) -> Vec<String> {
+        self.commands()
+            .iter()
+            .map(|command| command.name().to_string())
+            .chain(["now".into()])
+            .collect()
+    }
+
+    async fn playing(&self) -> Option<crate::external::lastfm::Response> {
+        playing(&self.lastfm).await
+    }
+
+    fn context(&self, invocation: &Invocation<'_>) -> RequestContext {
+        context(&self.config.load().ssh.hostname, invocation)
+    }
+}
+
+/// The context pages are rendered with
+fn context(hostname: &str, invocation: &Invocation<'_>) -> RequestContext {
+    RequestContext {
+        hostname: hostname.into(),
+        https: true,
+        user_agent: "ssh".into(),
+        ident: Ident {
+            i2p: false,
+            data: invocation
+                .client
+                .map(|ip| ip.to_string())
+                .unwrap_or_default(),
+            ipaddr: invocation.client,
+        },
+        mm_asn: None,
+        mm_city: None,
+        render: invocation.render,
+        trace_id: None,
+    }
+}
+
+async fn playing(lastfm: &Option<Arc<LastFM>>) -> Option<crate::external::lastfm::Response> {
+    match lastfm {
+        Some(lastfm) => lastfm.get_playing().await.result,
+        None => None,
+    }
+}
+
+/// A page of the website, which pages exist changes with the content
+struct PageCommand {
+    name: String,
+    page: Arc<ContentPage>,
+    lastfm: Option<Arc<LastFM>>,
+    hostname: String,
+}
+
+#[async_trait]
+impl Command for PageCommand {
+    fn id(&self) -> &'static str {
+        "page"
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn help(&self) -> &str {
+        &self.page.title
+    }
+
+    async fn execute(&self, invocation: &Invocation<'_>) -> Objects {
+        let ctx = context(&self.hostname, invocation);
+        let mut data = DynamicData::default();
+        if self.page.uses(DynamicBlock::NowPlaying) {
+            data.playing = playing(&self.lastfm).await;
+        }
+        self.page.objects(&ctx, &data).into()
+    }
+}
+
+/// What I'm listening to, like `/now`
+pub struct Now(pub Arc<Site>);
+
+#[async_trait]
+impl Command for Now {
+    fn id(&self) -> &'static str {
+        "now"
+    }
+
+    fn help(&self) -> &str {
+        "What I'm listening to right now"
+    }
+
+    fn available(&self, _: Frontend) -> bool {
+        true
+    }
+
+    async fn execute(&self, invocation: &Invocation<'_>) -> Objects {
+        let ctx = self.0.context(invocation);
+        let data = DynamicData {
+            playing: self.0.playing().await,
+        };
+        let mut objects = content::now_playing(&ctx, &data);
+        objects.extend(common::footer::footer().into_iter().flatten());
+        objects.into()
+    }
+}
+
+/// The sections in a full-screen browser, without a pty only a list of them
+pub struct Browse(pub Arc<Site>);
+
+#[async_trait]
+impl Command for Browse {
+    fn id(&self) -> &'static str {
+        "browse"
+    }
+
+    fn help(&self) -> &str {
+        "Browse the site with the arrow keys"
+    }
+
+    fn full_screen(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, _: &Invocation<'_>) -> Objects {
+        let hostname = self.0.config.load().ssh.hostname.clone();
+        vec![
+            TextBlobBuilder::new(format!("Sections: {}\n\n", self.0.sections().join(", "))).into(),
+            TextBlobBuilder::new(format!(
+                "The browser needs a terminal, try `ssh -t {hostname} browse`\n"
+            ))
+            .style(Style::new().fg(Bit4Color::YELLOW))
+            .into(),
+        ]
+        .into()
+    }
+}
+
+/// Draws the pixels of `/dev/canvas`
+pub struct Canvas;
+
+#[async_trait]
+impl Command for Canvas {
+    fn id(&self) -> &'static str {
+        "canvas"
+    }
+
+    fn help(&self) -> &str {
+        "Draws colored pixels, NL starts a new line"
+    }
+
+    fn args(&self) -> &[Arg] {
+        const ARGS: &[Arg] = &[Arg::new("pixels", "Colors like BLBLRRNLYY")
+            .rest()
+            .values(colors)];
+        ARGS
+    }
+
+    fn available(&self, _: Frontend) -> bool {
+        true
+    }
+
+    async fn execute(&self, invocation: &Invocation<'_>) -> Objects {
+        let args = invocation.args;
+        let q = (!args.is_empty()).then(|| args.concat());
+        common::canvas::canvas(q)
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+fn colors() -> Vec<String> {
+    let mut colors = COLOR_MAPPING.keys().cloned().collect::<Vec<_>>();
+    colors.sort();
+    colors.push("NL".into());
+    colors
+}
+
+/// `/` is `home`, `/portfolio` is `portfolio`, nested routes have no command
+fn page_command(route: &str) -> Option<String> {
+    match route.trim_start_matches('/') {
+        "" => Some("home".into()),
+        route if !route.contains('/') => Some(route.into()),
+        _ => None,
+    }
+}
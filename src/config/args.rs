@@ -0,0 +1,68 @@
+use std::{env, path::PathBuf, sync::OnceLock};
+
+use crate::config::error::ConfigError;
+
+static ARGS: OnceLock<Args> = OnceLock::new();
+
+/// Command line flags, parsed by hand as there are only a few
+#[derive(Debug, Default)]
+pub struct Args {
+    // --config <path>
+    pub config_path: Option<PathBuf>,
+    // --set <section.key=value>, applied in order after env vars
+    pub overrides: Vec<(String, String)>,
+    pub print_config: bool,
+    pub check_config: bool,
+    pub generate_example: bool,
+}
+
+impl Args {
+    /// The flags parsed at startup, reloads apply the same `--config` and `--set` flags
+    pub fn get() -> &'static Args {
+        ARGS.get_or_init(Args::default)
+    }
+
+    /// Parses the command line, only the first call stores its result
+    pub fn init() -> Result<&'static Args, ConfigError> {
+        let args = Self::parse()?;
+        Ok(ARGS.get_or_init(|| args))
+    }
+
+    fn parse() -> Result<Self, ConfigError> {
+        let mut args = Self::default();
+        let mut iter = env::args().skip(1);
+
+        while let Some(arg) = iter.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+                _ => (arg.clone(), None),
+            };
+
+            let mut value = |flag: &str| {
+                inline
+                    .map(String::from)
+                    .or_else(|| iter.next())
+                    .ok_or_else(|| ConfigError::Arg(format!("{flag} needs a value")))
+            };
+
+            match flag.as_str() {
+                "--config" => args.config_path = Some(value("--config")?.into()),
+                "--set" => {
+                    let set = value("--set")?;
+                    let Some((key, value)) = set.split_once('=') else {
+                        return Err(ConfigError::Arg(format!(
+                            "--set expects section.key=value, got {set}"
+                        )));
+                    };
+                    args.overrides.push((key.trim().into(), value.into()));
+                }
+                "--print-config" => args.print_config = true,
+                "--check-config" => args.check_config = true,
+                "--generate-example" => args.generate_example = true,
+                _ => return Err(ConfigError::Arg(format!("unknown argument {arg}"))),
+            }
+        }
+
+        Ok(args)
+    }
+}
@@ -22,4 +22,39 @@ pub enum ConfigError {
 
     #[error("failed to serialize config: {0}")]
     Serialize(#[from] toml::ser::Error),
+
+    #[error("{0}")]
+    Arg(String),
+
+    #[error("invalid {key}: {reason}")]
+    Invalid { key: &'static str, reason: String },
+
+    #[error("{key} must be set when {required_by} is true")]
+    Missing {
+        key: &'static str,
+        required_by: &'static str,
+    },
+
+    #[error("{key}: {value:?} is not a IP address")]
+    InvalidIp { key: &'static str, value: String },
+
+    #[error("{key}: {value:?} is not a valid http(s) URL")]
+    InvalidUrl { key: &'static str, value: String },
+
+    #[error("{key}: {path} does not exist or is not a file")]
+    MissingFile { key: &'static str, path: String },
+
+    #[error("{key} is still the placeholder {value:?}")]
+    Placeholder { key: &'static str, value: String },
+
+    #[error("{} config problem(s):\n{}", .0.len(), list(.0))]
+    Validation(Vec<ConfigError>),
+}
+
+fn list(errors: &[ConfigError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("  - {e}"))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
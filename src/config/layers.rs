@@ -0,0 +1,409 @@
+use std::{collections::BTreeMap, env, fmt, path::PathBuf};
+
+use toml::{Table, Value};
+use tracing::warn;
+
+use crate::config::{args::Args, error::ConfigError, types::Config};
+
+/// Env vars overriding config keys: `KYBE_BACKEND__WEBSERVER__UMAMI__ID` sets `webserver.umami.id`
+const ENV_PREFIX: &str = "KYBE_BACKEND__";
+/// `KYBE_BACKEND__DISCORD_BOT__TOKEN_FILE` reads `discord_bot.token` from a file (Docker secrets)
+const ENV_FILE_SUFFIX: &str = "_FILE";
+
+/// Keys never printed by `--print-config`
+const SECRETS: &[&str] = &[
+    "discord_bot.token",
+    "discord_bot.translator.token",
+    "webserver.api_token",
+    "lastfm.token",
+    "wolfram_alpha.token",
+    "privacy.hash_key",
+    // webhook urls contain their token
+    "alerts.sinks.url",
+    "alerts.sinks.token",
+];
+
+/// Where the value of a key came from
+#[derive(Clone, Debug)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Env(String),
+    EnvFile(String),
+    Cli,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "file {}", path.display()),
+            Source::Env(var) => write!(f, "env {var}"),
+            Source::EnvFile(var) => write!(f, "env {var} (file)"),
+            Source::Cli => write!(f, "--set"),
+        }
+    }
+}
+
+/// A config merged from all layers with the source of every key
+pub struct Layered {
+    pub config: Config,
+    table: Table,
+    sources: BTreeMap<String, Source>,
+}
+
+/// Merges defaults, the TOML file, `KYBE_BACKEND__*` env vars and `--set` flags (in that order)
+///
+/// A missing file is an error as the caller decides whether to create it.
+pub fn load(args: &Args, path: PathBuf, contents: &str) -> Result<Layered, ConfigError> {
+    let mut layered = Layered {
+        config: Config::default(),
+        table: Table::try_from(Config::default())?,
+        sources: BTreeMap::new(),
+    };
+    let defaults = layered.table.clone();
+    layered.record("", &defaults, &Source::Default);
+
+    let file: Table = toml::from_str(contents)?;
+    layered.merge("", file, &Source::File(path));
+
+    let mut vars = env::vars()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect::<Vec<_>>();
+    // `KEY_FILE` sorts after `KEY`, so the file wins when both are set
+    vars.sort();
+
+    for (name, value) in vars {
+        let (key, value, source) = match name.strip_suffix(ENV_FILE_SUFFIX) {
+            Some(key) => {
+                let value = std::fs::read_to_string(&value).map_err(|e| {
+                    ConfigError::Arg(format!("{name}: failed to read {value}: {e}"))
+                })?;
+                (key, value.trim().to_string(), Source::EnvFile(name.clone()))
+            }
+            None => (name.as_str(), value, Source::Env(name.clone())),
+        };
+
+        let key = key[ENV_PREFIX.len()..].to_lowercase().replace("__", ".");
+        layered.set(&key, &value, source);
+    }
+
+    for (key, value) in &args.overrides {
+        layered.set(key, value, Source::Cli);
+    }
+
+    layered.config = Value::Table(layered.table.clone()).try_into()?;
+
+    Ok(layered)
+}
+
+impl Layered {
+    fn record(&mut self, prefix: &str, table: &Table, source: &Source) {
+        for (key, value) in table {
+            let path = join(prefix, key);
+            match value {
+                Value::Table(table) => self.record(&path, table, source),
+                _ => {
+                    self.sources.insert(path, source.clone());
+                }
+            }
+        }
+    }
+
+    fn merge(&mut self, prefix: &str, layer: Table, source: &Source) {
+        for (key, value) in layer {
+            let path = join(prefix, &key);
+            match value {
+                Value::Table(table) => self.merge(&path, table, source),
+                value => {
+                    self.sources.insert(path.clone(), source.clone());
+                    self.insert(&path, value);
+                }
+            }
+        }
+    }
+
+    /// Sets a single key from a string, parsed according to the type the key already has
+    fn set(&mut self, key: &str, raw: &str, source: Source) {
+        let mut table = Some(&self.table);
+        let mut current = None;
+        for part in key.split('.') {
+            current = table.and_then(|t| t.get(part));
+            table = current.and_then(Value::as_table);
+        }
+
+        let value = match current {
+            Some(Value::Table(_)) => {
+                warn!("config: {source} sets {key}, which is a section and not a key");
+                return;
+            }
+            Some(Value::String(_)) => Value::String(raw.into()),
+            Some(_) => toml_literal(raw).unwrap_or_else(|| {
+                warn!("config: {source} has an invalid value for {key}, using it as a string");
+                Value::String(raw.into())
+            }),
+            None => {
+                warn!("config: {source} sets unknown key {key}");
+                toml_literal(raw).unwrap_or_else(|| Value::String(raw.into()))
+            }
+        };
+
+        self.sources.insert(key.into(), source);
+        self.insert(key, value);
+    }
+
+    fn insert(&mut self, key: &str, value: Value) {
+        let mut table = &mut self.table;
+        let mut parts = key.split('.').peekable();
+
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                table.insert(part.into(), value);
+                return;
+            }
+
+            let entry = table
+                .entry(part)
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            let Value::Table(next) = entry else {
+                return;
+            };
+            table = next;
+        }
+    }
+
+    /// The merged config as TOML with the source of every key and secrets redacted
+    pub fn dump(&self) -> String {
+        let mut output = String::from("# merged config, secrets are redacted\n");
+        self.dump_table("", &self.table, &mut output);
+        output
+    }
+
+    fn dump_table(&self, prefix: &str, table: &Table, output: &mut String) {
+        for (key, value) in table
+            .iter()
+            .filter(|(_, v)| !v.is_table() && tables(v).is_none())
+        {
+            let path = join(prefix, key);
+            let value = if SECRETS.contains(&path.as_str())
+                && value.as_str().is_none_or(|s| !s.is_empty())
+            {
+                Value::String("<redacted>".into())
+            } else {
+                value.clone()
+            };
+
+            output.push_str(&format!("{key} = {value} # {}\n", self.source(&path)));
+        }
+
+        for (key, value) in table {
+            let path = join(prefix, key);
+            if let Value::Table(table) = value {
+                output.push_str(&format!("\n[{path}]\n"));
+                self.dump_table(&path, table, output);
+            } else if let Some(tables) = tables(value) {
+                for table in tables {
+                    output.push_str(&format!("\n[[{path}]]\n"));
+                    self.dump_table(&path, table, output);
+                }
+            }
+        }
+    }
+
+    /// Keys inside arrays (`alerts.sinks.url`) come from wherever the array came from
+    fn source(&self, path: &str) -> String {
+        let mut path = path;
+        loop {
+            if let Some(source) = self.sources.get(path) {
+                return source.to_string();
+            }
+            match path.rsplit_once('.') {
+                Some((parent, _)) => path = parent,
+                None => return "unknown".into(),
+            }
+        }
+    }
+}
+
+/// The tables of a non-empty array of tables (`[[alerts.sinks]]`)
+fn tables(value: &Value) -> Option<Vec<&Table>> {
+    let array = value.as_array().filter(|a| !a.is_empty())?;
+    array.iter().map(Value::as_table).collect()
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.into()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// Parses `raw` as a TOML value like `true`, `10` or `["a", "b"]`
+fn toml_literal(raw: &str) -> Option<Value> {
+    toml::from_str::<Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut t| t.remove("value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, PoisonError};
+
+    use super::*;
+
+    // load() reads the environment, the test setting env vars must not run alongside the others
+    static ENV: Mutex<()> = Mutex::new(());
+
+    fn layered(file: &str, overrides: &[(&str, &str)]) -> Result<Layered, ConfigError> {
+        let args = Args {
+            overrides: overrides
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            ..Args::default()
+        };
+        let _env = ENV.lock().unwrap_or_else(PoisonError::into_inner);
+        load(&args, "config.toml".into(), file)
+    }
+
+    fn keys(error: ConfigError) -> Vec<&'static str> {
+        let ConfigError::Validation(errors) = error else {
+            panic!("not a validation error: {error}");
+        };
+        errors
+            .into_iter()
+            .map(|error| match error {
+                ConfigError::Invalid { key, .. }
+                | ConfigError::Missing { key, .. }
+                | ConfigError::InvalidIp { key, .. }
+                | ConfigError::InvalidUrl { key, .. }
+                | ConfigError::MissingFile { key, .. }
+                | ConfigError::Placeholder { key, .. } => key,
+                error => panic!("unexpected error: {error}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn file_overrides_defaults() -> Result<(), ConfigError> {
+        let layered = layered("[lastfm]\nusername = \"file\"\n", &[])?;
+        assert_eq!(layered.config.lastfm.username.as_deref(), Some("file"));
+        assert_eq!(layered.config.lastfm.interval_secs, Some(10));
+        assert_eq!(layered.source("lastfm.username"), "file config.toml");
+        assert_eq!(layered.source("lastfm.interval_secs"), "default");
+        Ok(())
+    }
+
+    #[test]
+    fn set_overrides_file_in_order() -> Result<(), ConfigError> {
+        let layered = layered(
+            "[lastfm]\ninterval_secs = 20\n",
+            &[
+                ("lastfm.interval_secs", "30"),
+                ("lastfm.interval_secs", "40"),
+                ("webserver.behind_proxy", "true"),
+            ],
+        )?;
+        assert_eq!(layered.config.lastfm.interval_secs, Some(40));
+        assert!(layered.config.webserver.behind_proxy);
+        assert_eq!(layered.source("lastfm.interval_secs"), "--set");
+        Ok(())
+    }
+
+    #[test]
+    fn set_keeps_the_type_of_the_key() -> Result<(), ConfigError> {
+        assert!(layered("", &[("lastfm.interval_secs", "often")]).is_err());
+
+        // a string key takes the value as it is, even if it looks like a number
+        let layered = layered("", &[("webserver.api_token", "1234")])?;
+        assert_eq!(layered.config.webserver.api_token, "1234");
+        Ok(())
+    }
+
+    #[test]
+    fn env_overrides_file_and_set_overrides_env() -> Result<(), ConfigError> {
+        let secret = env::temp_dir().join(format!("kybe-backend-test-{}", std::process::id()));
+        std::fs::write(&secret, "from-file\n").map_err(ConfigError::WriteFile)?;
+
+        let vars = [
+            ("KYBE_BACKEND__LASTFM__USERNAME", "env".into()),
+            ("KYBE_BACKEND__LASTFM__INTERVAL_SECS", "30".into()),
+            ("KYBE_BACKEND__LASTFM__TOKEN", "plain".into()),
+            (
+                "KYBE_BACKEND__LASTFM__TOKEN_FILE",
+                secret.display().to_string(),
+            ),
+        ];
+
+        let args = Args {
+            overrides: vec![("lastfm.interval_secs".into(), "40".into())],
+            ..Args::default()
+        };
+        let file = "[lastfm]\nusername = \"file\"\ninterval_secs = 20\n";
+
+        let result = {
+            let _env = ENV.lock().unwrap_or_else(PoisonError::into_inner);
+            for (name, value) in &vars {
+                // SAFETY: every other test reading the environment holds the ENV lock
+                unsafe { env::set_var(name, value) };
+            }
+            let result = load(&args, "config.toml".into(), file);
+            for (name, _) in &vars {
+                // SAFETY: see above
+                unsafe { env::remove_var(name) };
+            }
+            result
+        };
+        let _ = std::fs::remove_file(&secret);
+        let layered = result?;
+
+        assert_eq!(layered.config.lastfm.username.as_deref(), Some("env"));
+        assert_eq!(layered.config.lastfm.interval_secs, Some(40));
+        // the file variant wins over the plain one
+        assert_eq!(layered.config.lastfm.token.as_deref(), Some("from-file"));
+        assert_eq!(
+            layered.source("lastfm.username"),
+            "env KYBE_BACKEND__LASTFM__USERNAME"
+        );
+        assert_eq!(
+            layered.source("lastfm.token"),
+            "env KYBE_BACKEND__LASTFM__TOKEN_FILE (file)"
+        );
+        assert!(!layered.dump().contains("from-file"));
+        Ok(())
+    }
+
+    #[test]
+    fn validation_reports_every_problem() -> Result<(), ConfigError> {
+        let layered = layered(
+            "[lastfm]\nenable = true\ninterval_secs = 0\n[alerts]\nrate_limit = 0\n",
+            &[("logger.level", "kybe_backend=loud")],
+        )?;
+        let Err(error) = layered.config.validate() else {
+            panic!("invalid config passed validation");
+        };
+        assert!(error.to_string().starts_with("6 config problem(s):\n"));
+        assert_eq!(
+            keys(error),
+            [
+                "webserver.api_token",
+                "lastfm.token",
+                "lastfm.username",
+                "lastfm.interval_secs",
+                "alerts.rate_limit",
+                "logger.level",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn validation_passes_once_fixed() -> Result<(), ConfigError> {
+        let layered = layered("[webserver]\napi_token = \"secret\"\n", &[])?;
+        layered.config.validate()
+    }
+}
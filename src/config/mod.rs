@@ -1,23 +1,49 @@
+use crate::config::args::Args;
 use crate::config::error::ConfigError;
+use crate::config::layers::Layered;
 use crate::config::types::{
-    Config, DiscordBotConfig, LastFMConfig, LoggerConfig, MaxMindConfig, TranslatorConfig,
-    UmamiConfig, WebserverConfig, WolframAlphaConfig,
+    AlertsConfig, Config, DiscordBotConfig, HealthConfig, HoneypotConfig, LastFMConfig, LogFormat,
+    LogRotation, LoggerConfig, MaxMindConfig, OtlpConfig, PrivacyConfig, PrivacyMode, SshConfig,
+    TlsConfig, TranslatorConfig, UmamiConfig, WebserverConfig, WolframAlphaConfig,
 };
+use crate::webserver::readiness::Dependency;
+use crate::webserver::render::negotiate::DEFAULT_CLI_USER_AGENTS;
+use arc_swap::ArcSwap;
+use std::collections::BTreeMap;
 use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
 use tokio::fs;
 use tracing::{error, info, warn};
 
+pub mod args;
 pub mod error;
+pub mod layers;
+pub mod reload;
 pub mod types;
+mod validate;
+
+/// The current config, swapped out when config.toml is reloaded
+pub type SharedConfig = Arc<ArcSwap<Config>>;
+
+/// Overrides the config path like `--config` does
+const CONFIG_PATH_ENV: &str = "KYBE_BACKEND_CONFIG";
 
 const DEFAULT_CONFIG_URL: &str =
     "https://git.kybe.xyz/2kybe3/kybe-backend/src/branch/main/config/config.toml.example";
 
 impl Config {
     pub async fn init() -> Result<Self, ConfigError> {
-        let args: Vec<String> = env::args().collect();
-        if args.iter().any(|arg| arg == "--generate-example") {
+        let args = match Args::init() {
+            Ok(args) => args,
+            Err(e) => {
+                error!("{e}");
+                std::process::exit(2);
+            }
+        };
+
+        if args.generate_example {
             let time = Instant::now();
             info!("Generating config/config.toml.example");
             Self::create_local_default().await?;
@@ -28,7 +54,36 @@ impl Config {
             std::process::exit(0)
         }
 
-        match Self::load_or_create().await {
+        if args.print_config {
+            match Self::load_layered().await {
+                Ok(layered) => {
+                    print!("{}", layered.dump());
+                    std::process::exit(0)
+                }
+                Err(e) => {
+                    error!("Failed to load config: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if args.check_config {
+            match Self::load().await.and_then(|cfg| cfg.validate()) {
+                Ok(()) => {
+                    info!("{} is valid", Self::path()?.display());
+                    std::process::exit(0)
+                }
+                Err(e) => {
+                    error!("{e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        match Self::load_or_create()
+            .await
+            .and_then(|cfg| cfg.validate().map(|_| cfg))
+        {
             Ok(cfg) => Ok(cfg),
             Err(e) => {
                 error!("Failed to load config: {e}");
@@ -46,7 +101,7 @@ impl Config {
             }
 
             Err(ConfigError::ReadFile(e)) if e.kind() == std::io::ErrorKind::NotFound => {
-                info!("creating default config/config.toml");
+                info!("creating default {}", Self::path()?.display());
                 Self::create_default().await?;
                 info!("config created in {} ms", start.elapsed().as_millis());
                 if let Err(e) = Self::load().await {
@@ -56,7 +111,7 @@ impl Config {
                     );
                     std::process::exit(1);
                 } else {
-                    info!("Default config/config.toml created! Please edit");
+                    info!("Default {} created! Please edit", Self::path()?.display());
                     std::process::exit(0);
                 }
             }
@@ -65,24 +120,38 @@ impl Config {
         }
     }
 
-    pub async fn load() -> Result<Self, ConfigError> {
-        let path = env::current_dir()
+    /// `--config`, then `KYBE_BACKEND_CONFIG`, then config/config.toml
+    pub fn path() -> Result<PathBuf, ConfigError> {
+        let path = Args::get()
+            .config_path
+            .clone()
+            .or_else(|| env::var_os(CONFIG_PATH_ENV).map(PathBuf::from))
+            .unwrap_or("config/config.toml".into());
+
+        Ok(env::current_dir()
             .map_err(ConfigError::CurrentDir)?
-            .join("config/config.toml");
+            .join(path))
+    }
+
+    pub async fn load() -> Result<Self, ConfigError> {
+        Ok(Self::load_layered().await?.config)
+    }
+
+    /// Loads the config file with env var and `--set` overrides applied on top
+    pub async fn load_layered() -> Result<Layered, ConfigError> {
+        let path = Self::path()?;
         let contents = fs::read_to_string(&path)
             .await
             .map_err(ConfigError::ReadFile)?;
-        Ok(toml::from_str(&contents)?)
+        layers::load(Args::get(), path, &contents)
     }
     pub async fn create_default() -> Result<(), ConfigError> {
-        let path = env::current_dir()
-            .map_err(ConfigError::CurrentDir)?
-            .join("config/config.toml");
+        let path = Self::path()?;
 
         let resp = reqwest::get(DEFAULT_CONFIG_URL).await?;
         let content = resp.text().await?;
 
-        fs::create_dir_all("config")
+        fs::create_dir_all(path.parent().unwrap_or(&path))
             .await
             .map_err(ConfigError::CreateDir)?;
         fs::write(path, content)
@@ -144,6 +213,41 @@ impl Default for Config {
             },
             logger: LoggerConfig {
                 file_logger_enabled: true,
+                format: LogFormat::Full,
+                dir: "./config/log".into(),
+                rotation: LogRotation::Daily,
+                max_files: 0,
+                max_total_mb: 0,
+                level: "info".into(),
+                targets: BTreeMap::from([("kybe_backend".into(), "debug".into())]),
+                otlp: OtlpConfig {
+                    enabled: false,
+                    endpoint: "http://localhost:4318/v1/traces".into(),
+                    service_name: "kybe-backend".into(),
+                    sample_ratio: 1.0,
+                    timeout_secs: 10,
+                },
+            },
+            alerts: AlertsConfig {
+                dedup_secs: 3600,
+                rate_limit: 5,
+                summary_secs: 3600,
+                sinks: vec![],
+            },
+            health: HealthConfig {
+                critical: vec![Dependency::MaxmindCity, Dependency::MaxmindAsn],
+                lastfm_max_age_secs: 300,
+                maxmind_max_age_days: 30,
+            },
+            privacy: PrivacyConfig {
+                mode: PrivacyMode::Truncate,
+                hash_key: "".into(),
+                honeypot: HoneypotConfig {
+                    enabled: false,
+                    dir: "./config/honeypot".into(),
+                    max_total_mb: 100,
+                    max_age_days: 30,
+                },
             },
             webserver: WebserverConfig {
                 behind_proxy: false,
@@ -153,10 +257,29 @@ impl Default for Config {
                 behind_i2p: false,
                 i2p_ip: Some("".into()),
                 i2p_header: Some("X-I2P-DestHash".into()),
+                cli_user_agents: Some(
+                    DEFAULT_CLI_USER_AGENTS
+                        .iter()
+                        .map(|s| s.to_string())
+                        .collect(),
+                ),
                 umami: UmamiConfig {
                     script_path: Some("https://uma.kybe.xyz/".into()),
                     id: Some("umami-id".into()),
                 },
+                listen: vec!["0.0.0.0:3000".into()],
+                tls: TlsConfig {
+                    enabled: false,
+                    cert: "./config/tls/cert.pem".into(),
+                    key: "./config/tls/key.pem".into(),
+                },
+            },
+            ssh: SshConfig {
+                listen: vec!["0.0.0.0:2222".into()],
+                host_key: "./config/ssh_host_ed25519".into(),
+                hostname: "kybe.xyz".into(),
+                authorized_keys: "./config/authorized_keys".into(),
+                audit_log: "./config/audit.log".into(),
             },
         }
     }
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use toml::{Table, Value};
+use tracing::{error, info, warn};
+
+use crate::config::{SharedConfig, types::Config};
+
+/// Keys only read at startup, changing them needs a restart
+const RESTART_REQUIRED: &[&str] = &[
+    "discord_bot",
+    "wolfram_alpha",
+    "maxmind",
+    "logger.file_logger_enabled",
+    "logger.format",
+    "logger.dir",
+    "logger.rotation",
+    "logger.max_files",
+    "logger.max_total_mb",
+    "logger.otlp",
+    "lastfm.enable",
+    "lastfm.token",
+    "lastfm.username",
+    "webserver.listen",
+    "webserver.tls",
+    "ssh",
+    "alerts",
+    "privacy.honeypot",
+];
+
+/// Watches config/config.toml (and SIGHUP) and swaps in the new config once it's valid
+pub fn spawn_watcher(config: SharedConfig) -> anyhow::Result<()> {
+    crate::watcher::spawn("config", Config::path()?, move || {
+        let config = Arc::clone(&config);
+        async move {
+            if let Err(e) = reload(&config).await {
+                error!("config reload failed, keeping the current config: {e}");
+            }
+        }
+    })
+}
+
+/// Loads and validates config/config.toml and swaps it in, returns the keys which changed
+pub async fn reload(config: &SharedConfig) -> anyhow::Result<Vec<String>> {
+    let new = Config::load().await?;
+    new.validate()?;
+
+    let changed = changed_keys(&config.load(), &new);
+    if changed.is_empty() {
+        info!("config reloaded, nothing changed");
+        return Ok(changed);
+    }
+
+    let mut sections = changed
+        .iter()
+        .map(|key| key.split('.').next().unwrap_or(key))
+        .collect::<Vec<_>>();
+    sections.dedup();
+
+    for key in &changed {
+        if restart_required(key) {
+            warn!("config: {key} changed, restart required to apply it");
+        }
+    }
+
+    // the log filter is the only logger setting which can be swapped at runtime
+    let filter_changed = changed
+        .iter()
+        .any(|key| key == "logger.level" || key.starts_with("logger.targets."));
+    if filter_changed {
+        match crate::logger::reset_filter(&new.logger) {
+            Ok(filter) => info!("log filter changed to {filter}"),
+            Err(e) => error!("failed to change the log filter: {e}"),
+        }
+    }
+
+    if changed.iter().any(|key| key.starts_with("privacy.")) {
+        crate::redact::configure(&new.privacy);
+    }
+
+    config.store(Arc::new(new));
+
+    info!(
+        "config reloaded, changed sections: {} ({})",
+        sections.join(", "),
+        changed.join(", ")
+    );
+    Ok(changed)
+}
+
+/// Whether a change of `key` (a dotted path) only applies after a restart
+pub fn restart_required(key: &str) -> bool {
+    RESTART_REQUIRED
+        .iter()
+        .any(|k| key == *k || key.starts_with(&format!("{k}.")))
+}
+
+/// Dotted paths of all keys which differ between the configs (values are left out, they may be secrets)
+fn changed_keys(old: &Config, new: &Config) -> Vec<String> {
+    match (Table::try_from(old), Table::try_from(new)) {
+        (Ok(old), Ok(new)) => {
+            let mut changed = Vec::new();
+            diff("", &old, &new, &mut changed);
+            changed
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            error!("failed to compare configs: {e}");
+            vec![]
+        }
+    }
+}
+
+fn diff(prefix: &str, old: &Table, new: &Table, changed: &mut Vec<String>) {
+    let mut keys = old.keys().chain(new.keys()).collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        match (old.get(key), new.get(key)) {
+            (Some(Value::Table(old)), Some(Value::Table(new))) => diff(&path, old, new, changed),
+            (old, new) if old != new => changed.push(path),
+            _ => {}
+        }
+    }
+}
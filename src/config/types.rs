@@ -1,13 +1,21 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::{alert::Severity, webserver::readiness::Dependency};
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Config {
     pub wolfram_alpha: WolframAlphaConfig,
     pub discord_bot: DiscordBotConfig,
     pub webserver: WebserverConfig,
+    pub ssh: SshConfig,
     pub maxmind: MaxMindConfig,
     pub lastfm: LastFMConfig,
     pub logger: LoggerConfig,
+    pub alerts: AlertsConfig,
+    pub health: HealthConfig,
+    pub privacy: PrivacyConfig,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -55,6 +63,49 @@ pub struct LastFMConfig {
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct LoggerConfig {
     pub file_logger_enabled: bool,
+    // used for stdout and the log files
+    pub format: LogFormat,
+    pub dir: String,
+    pub rotation: LogRotation,
+    // the oldest files are deleted when there are more (0 keeps all)
+    pub max_files: usize,
+    // ... or when all files together are larger (0 is unlimited)
+    pub max_total_mb: u64,
+    // default level and per-target levels, replaced by RUST_LOG when it's set
+    pub level: String,
+    pub targets: BTreeMap<String, String>,
+    pub otlp: OtlpConfig,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    Full,
+    Pretty,
+    Compact,
+    Json,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Never,
+}
+
+// OpenTelemetry trace export
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OtlpConfig {
+    pub enabled: bool,
+    // OTLP/HTTP traces endpoint, "" uses OTEL_EXPORTER_OTLP_ENDPOINT
+    pub endpoint: String,
+    pub service_name: String,
+    // share of new traces which are exported, requests with a traceparent keep its decision
+    pub sample_ratio: f64,
+    pub timeout_secs: u64,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -66,7 +117,37 @@ pub struct WebserverConfig {
     pub behind_i2p: bool,
     pub i2p_ip: Option<String>,
     pub i2p_header: Option<String>,
+    // User-Agent substrings (case insensitive) which get the terminal version of pages
+    pub cli_user_agents: Option<Vec<String>>,
     pub umami: UmamiConfig,
+    // "0.0.0.0:3000", "[::]:3000" or "unix:/run/kybe-backend/web.sock",
+    // list both 0.0.0.0 and [::] for dual-stack. Unix sockets need behind_proxy, their clients
+    // are told apart by proxy_header whatever proxy_ip is
+    pub listen: Vec<String>,
+    pub tls: TlsConfig,
+}
+
+// Serve HTTPS directly, the files are reloaded when they change
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TlsConfig {
+    pub enabled: bool,
+    pub cert: String,
+    pub key: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SshConfig {
+    // same format as webserver.listen
+    pub listen: Vec<String>,
+    // generated on first start if it doesn't exist
+    pub host_key: String,
+    // links in pages shown over SSH point to https://<hostname>
+    pub hostname: String,
+    // authorized_keys format, `role="admin"` or `role="viewer"` (the default) in the options,
+    // reloaded when it changes, without the file nobody gets the admin console
+    pub authorized_keys: String,
+    // JSONL, one line per login with a key and every admin command
+    pub audit_log: String,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -74,3 +155,96 @@ pub struct UmamiConfig {
     pub script_path: Option<String>,
     pub id: Option<String>,
 }
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct AlertsConfig {
+    // the same alert (severity and title) again within this window only counts towards the summary
+    pub dedup_secs: u64,
+    // alerts per sink per minute, the rest only count towards the summary
+    pub rate_limit: u32,
+    // how often suppressed alerts are summarized
+    pub summary_secs: u64,
+    pub sinks: Vec<AlertSinkConfig>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct AlertSinkConfig {
+    #[serde(flatten)]
+    pub kind: AlertSinkKind,
+    // alerts below this are only logged
+    #[serde(default)]
+    pub min_severity: Severity,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertSinkKind {
+    // POSTs the alert as JSON
+    Webhook {
+        url: String,
+    },
+    DiscordWebhook {
+        url: String,
+    },
+    // DM to discord_bot.admin_id using discord_bot.token
+    DiscordDm,
+    // https://ntfy.sh/<topic> or a self hosted server
+    Ntfy {
+        url: String,
+        token: Option<String>,
+    },
+    // piped to a sendmail compatible command until there is a SMTP client
+    Email {
+        to: String,
+        from: String,
+        #[serde(default = "default_sendmail")]
+        command: String,
+    },
+}
+
+// Thresholds for /health/ready, read on every request
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct HealthConfig {
+    // readiness fails when one of these is down or stale, the others only degrade it
+    pub critical: Vec<Dependency>,
+    // the Last.fm cache is stale when its last successful sync is older than this
+    pub lastfm_max_age_secs: u64,
+    // MaxMind databases are stale when they were built longer ago than this
+    pub maxmind_max_age_days: u64,
+}
+
+// How client IPs, commands and credentials end up in logs and traces
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PrivacyConfig {
+    pub mode: PrivacyMode,
+    // key for hashed IPs and user ids, a random key is used when empty so hashes change on restart
+    pub hash_key: String,
+    pub honeypot: HoneypotConfig,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyMode {
+    // IPs and commands are logged as they are, passwords are still masked
+    Off,
+    // IPv4 to /24 and IPv6 to /48, commands are masked
+    Truncate,
+    // IPs and user ids are replaced by a keyed hash, commands are masked
+    Hash,
+}
+
+// Records SSH sessions including their credentials, never written to the logs
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct HoneypotConfig {
+    pub enabled: bool,
+    // <session>.jsonl (events) and <session>.cast (asciinema), readable by the owner only
+    pub dir: String,
+    // the oldest sessions are deleted when all of them together are larger (0 is unlimited)
+    pub max_total_mb: u64,
+    // ... or when they were last written to longer ago (0 keeps them)
+    pub max_age_days: u64,
+}
+
+fn default_sendmail() -> String {
+    "sendmail".into()
+}
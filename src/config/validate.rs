@@ -0,0 +1,381 @@
+use std::{net::IpAddr, path::Path};
+
+use axum::http::HeaderName;
+use reqwest::Url;
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    config::{
+        error::ConfigError,
+        types::{AlertSinkKind, Config},
+    },
+    listener::ListenAddress,
+};
+
+/// Values from config.toml.example which have to be replaced before use
+const PLACEHOLDERS: &[&str] = &["CHANGE_ME", "DISCORD_TOKEN", "APP_ID"];
+
+impl Config {
+    /// Checks values which would otherwise only fail when they are used,
+    /// every problem is reported at once in a `ConfigError::Validation`
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+
+        self.validate_webserver(&mut errors);
+        self.validate_ssh(&mut errors);
+        self.validate_discord_bot(&mut errors);
+        self.validate_maxmind(&mut errors);
+        self.validate_lastfm(&mut errors);
+        self.validate_alerts(&mut errors);
+        self.validate_logger(&mut errors);
+        self.validate_privacy(&mut errors);
+
+        if self.wolfram_alpha.enabled {
+            secret(
+                &mut errors,
+                "wolfram_alpha.token",
+                "wolfram_alpha.enabled",
+                &self.wolfram_alpha.token,
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Validation(errors))
+        }
+    }
+
+    fn validate_webserver(&self, errors: &mut Vec<ConfigError>) {
+        let webserver = &self.webserver;
+
+        placeholder(errors, "webserver.api_token", &webserver.api_token);
+        if webserver.api_token.is_empty() {
+            errors.push(ConfigError::Invalid {
+                key: "webserver.api_token",
+                reason: "must not be empty".into(),
+            });
+        }
+
+        // behind_X, X_ip, X_header
+        let upstreams = [
+            (
+                ("webserver.behind_proxy", webserver.behind_proxy),
+                ("webserver.proxy_ip", &webserver.proxy_ip),
+                ("webserver.proxy_header", &webserver.proxy_header),
+            ),
+            (
+                ("webserver.behind_i2p", webserver.behind_i2p),
+                ("webserver.i2p_ip", &webserver.i2p_ip),
+                ("webserver.i2p_header", &webserver.i2p_header),
+            ),
+        ];
+
+        for ((enable_key, enabled), (ip_key, ip), (header_key, header)) in upstreams {
+            match set(ip) {
+                Some(ip) if ip.parse::<IpAddr>().is_err() => errors.push(ConfigError::InvalidIp {
+                    key: ip_key,
+                    value: ip.into(),
+                }),
+                None if enabled => errors.push(ConfigError::Missing {
+                    key: ip_key,
+                    required_by: enable_key,
+                }),
+                _ => {}
+            }
+
+            match set(header) {
+                Some(header) if HeaderName::try_from(header).is_err() => {
+                    errors.push(ConfigError::Invalid {
+                        key: header_key,
+                        reason: format!("{header:?} is not a valid header name"),
+                    })
+                }
+                None if enabled => errors.push(ConfigError::Missing {
+                    key: header_key,
+                    required_by: enable_key,
+                }),
+                _ => {}
+            }
+        }
+
+        if let Some(path) = set(&webserver.umami.script_path) {
+            url(errors, "webserver.umami.script_path", path);
+        }
+
+        listen(errors, "webserver.listen", &webserver.listen);
+        // Unix socket clients all have the same address, they'd share one rate limit
+        let unix = webserver
+            .listen
+            .iter()
+            .any(|address| matches!(address.parse(), Ok(ListenAddress::Unix(_))));
+        if unix && !webserver.behind_proxy {
+            errors.push(ConfigError::Invalid {
+                key: "webserver.listen",
+                reason: "unix: addresses need webserver.behind_proxy, only the proxy's header tells their clients apart".into(),
+            });
+        }
+        if webserver.tls.enabled {
+            file(errors, "webserver.tls.cert", &webserver.tls.cert);
+            file(errors, "webserver.tls.key", &webserver.tls.key);
+        }
+    }
+
+    fn validate_discord_bot(&self, errors: &mut Vec<ConfigError>) {
+        let bot = &self.discord_bot;
+        if !bot.enable {
+            return;
+        }
+
+        secret(
+            errors,
+            "discord_bot.token",
+            "discord_bot.enable",
+            &Some(bot.token.clone()),
+        );
+
+        if bot.admin_id.parse::<u64>().is_err() {
+            errors.push(ConfigError::Invalid {
+                key: "discord_bot.admin_id",
+                reason: format!("{:?} is not a Discord user id", bot.admin_id),
+            });
+        }
+
+        if bot.translator.enabled {
+            match set(&bot.translator.url) {
+                Some(path) => url(errors, "discord_bot.translator.url", path),
+                None => errors.push(ConfigError::Missing {
+                    key: "discord_bot.translator.url",
+                    required_by: "discord_bot.translator.enabled",
+                }),
+            }
+        }
+    }
+
+    fn validate_maxmind(&self, errors: &mut Vec<ConfigError>) {
+        let dbs = [
+            ("maxmind.city", self.maxmind.city_enable, &self.maxmind.city),
+            ("maxmind.asn", self.maxmind.asn_enable, &self.maxmind.asn),
+        ];
+
+        for (key, enabled, path) in dbs {
+            if enabled {
+                file(errors, key, path);
+            }
+        }
+    }
+
+    fn validate_lastfm(&self, errors: &mut Vec<ConfigError>) {
+        let lastfm = &self.lastfm;
+        if !lastfm.enable {
+            return;
+        }
+
+        secret(errors, "lastfm.token", "lastfm.enable", &lastfm.token);
+        if set(&lastfm.username).is_none() {
+            errors.push(ConfigError::Missing {
+                key: "lastfm.username",
+                required_by: "lastfm.enable",
+            });
+        }
+        if lastfm.interval_secs == Some(0) {
+            errors.push(ConfigError::Invalid {
+                key: "lastfm.interval_secs",
+                reason: "must be at least 1".into(),
+            });
+        }
+    }
+
+    fn validate_alerts(&self, errors: &mut Vec<ConfigError>) {
+        let alerts = &self.alerts;
+
+        if alerts.rate_limit == 0 {
+            errors.push(ConfigError::Invalid {
+                key: "alerts.rate_limit",
+                reason: "must be at least 1".into(),
+            });
+        }
+        if alerts.summary_secs < 60 {
+            errors.push(ConfigError::Invalid {
+                key: "alerts.summary_secs",
+                reason: "must be at least 60".into(),
+            });
+        }
+
+        for sink in &alerts.sinks {
+            match &sink.kind {
+                AlertSinkKind::Webhook { url: sink_url }
+                | AlertSinkKind::DiscordWebhook { url: sink_url }
+                | AlertSinkKind::Ntfy { url: sink_url, .. } => {
+                    url(errors, "alerts.sinks.url", sink_url)
+                }
+                AlertSinkKind::DiscordDm => {
+                    let token = &self.discord_bot.token;
+                    if token.is_empty() || PLACEHOLDERS.contains(&token.as_str()) {
+                        errors.push(ConfigError::Invalid {
+                            key: "discord_bot.token",
+                            reason: "the discord_dm alert sink needs a bot token".into(),
+                        });
+                    }
+                    if self.discord_bot.admin_id.parse::<u64>().is_err() {
+                        errors.push(ConfigError::Invalid {
+                            key: "discord_bot.admin_id",
+                            reason: "the discord_dm alert sink needs a Discord user id".into(),
+                        });
+                    }
+                }
+                AlertSinkKind::Email { to, from, .. } => {
+                    for (key, value) in [("alerts.sinks.to", to), ("alerts.sinks.from", from)] {
+                        if !value.contains('@') {
+                            errors.push(ConfigError::Invalid {
+                                key,
+                                reason: format!("{value:?} is not a email address"),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_logger(&self, errors: &mut Vec<ConfigError>) {
+        let logger = &self.logger;
+        if let Err(e) = EnvFilter::try_new(&logger.level) {
+            errors.push(ConfigError::Invalid {
+                key: "logger.level",
+                reason: e.to_string(),
+            });
+        }
+        for (target, level) in &logger.targets {
+            if let Err(e) = EnvFilter::try_new(format!("{target}={level}")) {
+                errors.push(ConfigError::Invalid {
+                    key: "logger.targets",
+                    reason: format!("{target}: {e}"),
+                });
+            }
+        }
+        if logger.file_logger_enabled && logger.dir.is_empty() {
+            errors.push(ConfigError::Invalid {
+                key: "logger.dir",
+                reason: "must be set when file logging is enabled".into(),
+            });
+        }
+
+        let otlp = &logger.otlp;
+        if !otlp.enabled {
+            return;
+        }
+
+        if !otlp.endpoint.is_empty() {
+            url(errors, "logger.otlp.endpoint", &otlp.endpoint);
+        }
+        if !(0.0..=1.0).contains(&otlp.sample_ratio) {
+            errors.push(ConfigError::Invalid {
+                key: "logger.otlp.sample_ratio",
+                reason: "must be between 0.0 and 1.0".into(),
+            });
+        }
+        if otlp.timeout_secs == 0 {
+            errors.push(ConfigError::Invalid {
+                key: "logger.otlp.timeout_secs",
+                reason: "must be at least 1".into(),
+            });
+        }
+    }
+
+    fn validate_ssh(&self, errors: &mut Vec<ConfigError>) {
+        listen(errors, "ssh.listen", &self.ssh.listen);
+
+        // admin commands have to be audited
+        if !self.ssh.authorized_keys.is_empty() && self.ssh.audit_log.is_empty() {
+            errors.push(ConfigError::Invalid {
+                key: "ssh.audit_log",
+                reason: "must be set when ssh.authorized_keys is".into(),
+            });
+        }
+    }
+
+    fn validate_privacy(&self, errors: &mut Vec<ConfigError>) {
+        let honeypot = &self.privacy.honeypot;
+        if !honeypot.enabled {
+            return;
+        }
+
+        if honeypot.dir.is_empty() {
+            errors.push(ConfigError::Invalid {
+                key: "privacy.honeypot.dir",
+                reason: "must be set when the honeypot is enabled".into(),
+            });
+        } else if self.logger.file_logger_enabled
+            && Path::new(&honeypot.dir).starts_with(&self.logger.dir)
+        {
+            // the log files are readable by more than the owner and get pruned
+            errors.push(ConfigError::Invalid {
+                key: "privacy.honeypot.dir",
+                reason: "must not be inside logger.dir".into(),
+            });
+        }
+    }
+}
+
+/// An empty string counts as not set, config.toml.example uses `""` for unset values
+fn set(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.is_empty())
+}
+
+fn placeholder(errors: &mut Vec<ConfigError>, key: &'static str, value: &str) {
+    if PLACEHOLDERS.contains(&value) {
+        errors.push(ConfigError::Placeholder {
+            key,
+            value: value.into(),
+        });
+    }
+}
+
+/// A token required by an enabled feature
+fn secret(
+    errors: &mut Vec<ConfigError>,
+    key: &'static str,
+    required_by: &'static str,
+    value: &Option<String>,
+) {
+    match set(value) {
+        Some(value) => placeholder(errors, key, value),
+        None => errors.push(ConfigError::Missing { key, required_by }),
+    }
+}
+
+fn url(errors: &mut Vec<ConfigError>, key: &'static str, value: &str) {
+    if Url::parse(value).is_ok_and(|u| matches!(u.scheme(), "http" | "https")) {
+        return;
+    }
+
+    errors.push(ConfigError::InvalidUrl {
+        key,
+        value: value.into(),
+    });
+}
+
+fn file(errors: &mut Vec<ConfigError>, key: &'static str, path: &str) {
+    if !Path::new(path).is_file() {
+        errors.push(ConfigError::MissingFile {
+            key,
+            path: path.into(),
+        });
+    }
+}
+
+fn listen(errors: &mut Vec<ConfigError>, key: &'static str, addresses: &[String]) {
+    if addresses.is_empty() {
+        errors.push(ConfigError::Invalid {
+            key,
+            reason: "needs at least one address".into(),
+        });
+    }
+
+    for address in addresses {
+        if let Err(reason) = address.parse::<ListenAddress>() {
+            errors.push(ConfigError::Invalid { key, reason });
+        }
+    }
+}
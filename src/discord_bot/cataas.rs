@@ -1,8 +1,8 @@
-use crate::discord_bot::{Context, Error, attach};
+use crate::discord_bot::{Context, Error, attach, span};
 use crate::external::cataas::{CATAASCatRequest, Filter, Fit, Position, Type};
 use poise::CreateReply;
 use poise::serenity_prelude::{AutocompleteChoice, CreateAttachment, CreateAutocompleteResponse};
-use tracing::error;
+use tracing::{Instrument, error};
 
 async fn autocomplete_tag(ctx: Context<'_>, partial: &str) -> CreateAutocompleteResponse {
     let tags = ctx.data().cataas.tags().await.to_owned();
@@ -91,10 +91,17 @@ pub async fn cat(
             .data()
             .cataas
             .get_cat_url(&request, tag.as_deref(), says.as_deref())
+            .instrument(span(&ctx).await)
             .await;
 
         match res {
-            Ok(Some(res)) => match ctx.data().cataas.get_image(&res.url).await {
+            Ok(Some(res)) => match ctx
+                .data()
+                .cataas
+                .get_image(&res.url)
+                .instrument(span(&ctx).await)
+                .await
+            {
                 Ok(bytes) => {
                     let ext = match res.mimetype.as_str() {
                         "image/png" => "png",
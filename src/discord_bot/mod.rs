@@ -1,12 +1,13 @@
 mod cataas;
 mod coords;
 mod maxmind;
+mod registry;
 mod show_me;
 mod traceroute;
 mod translator;
-mod version;
 mod wolframalpha;
 
+use crate::commands::Registry;
 use crate::config::types::Config;
 use crate::external::cataas::CATAAS;
 use crate::external::wolframalpha::WolframAlpha;
@@ -16,14 +17,20 @@ use poise::serenity_prelude as serenity;
 use poise::{CreateReply, FrameworkError};
 use reqwest::Client;
 use std::sync::Arc;
-use std::time::Duration;
-use tracing::error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::field::Empty;
+use tracing::{Span, error, info, info_span};
 
 type Error = anyhow::Error;
 pub(crate) type Context<'a> = poise::Context<'a, Data, Error>;
 
 pub const MAX_MSG_LENGTH: usize = 2000;
 
+/// Set while the gateway is connected, from Ready or Resumed until the shard disconnects (shown on /health/ready)
+pub static CONNECTED: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug)]
 pub struct Data {
     pub cataas: CATAAS,
@@ -32,25 +39,85 @@ pub struct Data {
     pub wolframalpha: WolframAlpha,
 }
 
-pub async fn init_bot(config: Arc<Config>, mm: Arc<MaxMind>) -> Result<(), Error> {
+/// Runs the bot until `shutdown` is cancelled, the shards are closed before returning
+pub async fn init_bot(
+    config: Arc<Config>,
+    mm: Arc<MaxMind>,
+    registry: Arc<Registry>,
+    shutdown: CancellationToken,
+) -> Result<(), Error> {
     let token = config.discord_bot.token.clone();
 
+    let mut commands = vec![
+        wolframalpha::wolframalpha(),
+        traceroute::traceroute(),
+        translator::detect(),
+        translator::languages(),
+        translator::translate(),
+        show_me::show_me(),
+        maxmind::maxmind(),
+        coords::coords(),
+        cataas::cat(),
+    ];
+    commands.extend(registry::commands(&registry));
+
     let framework = poise::Framework::builder()
 		.options(poise::FrameworkOptions {
-			commands: vec![
-                wolframalpha::wolframalpha(),
-                traceroute::traceroute(),
-				translator::detect(),
-				translator::languages(),
-				translator::translate(),
-                show_me::show_me(),
-				version::version(),
-				maxmind::maxmind(),
-                coords::coords(),
-				cataas::cat(),
-			],
+			commands,
+			pre_command: |ctx| {
+				Box::pin(async move {
+					let span = info_span!(
+						parent: None,
+						"slash_command",
+						otel.name = format!("/{}", ctx.command().qualified_name),
+						otel.kind = "server",
+						otel.status_code = Empty,
+						command = %ctx.command().qualified_name,
+						user = %crate::redact::id(ctx.author().id),
+					);
+					ctx.set_invocation_data(Invocation {
+						started: Instant::now(),
+						span,
+					})
+					.await
+				})
+			},
+			post_command: |ctx| Box::pin(record_command(ctx, "ok")),
 			on_error: |error: FrameworkError<'_, Data, Error>| {
-				Box::pin(async move { panic!("Error starting discord bot: {:?}", error) })
+				Box::pin(async move {
+					match error {
+						FrameworkError::Setup { error, .. } => {
+							panic!("Error starting discord bot: {:?}", error)
+						}
+						FrameworkError::Command { error, ctx, .. } => {
+							record_command(ctx, "error").await;
+							error!("/{} failed: {:?}", ctx.command().qualified_name, error);
+							let _ = ctx.say("The command failed due to an error.").await;
+						}
+						// argument parsing, checks, cooldowns, … the builtin handler replies with what went wrong
+						error => {
+							if let Err(e) = poise::builtins::on_error(error).await {
+								error!("Failed to handle a Discord error: {:?}", e);
+							}
+						}
+					}
+				})
+			},
+			event_handler: |_ctx, event, _framework, _data| {
+				Box::pin(async move {
+					match event {
+						serenity::FullEvent::Ready { .. } | serenity::FullEvent::Resume { .. } => {
+							CONNECTED.store(true, Ordering::Relaxed);
+						}
+						serenity::FullEvent::ShardStageUpdate { event }
+							if event.new != serenity::ConnectionStage::Connected =>
+						{
+							CONNECTED.store(false, Ordering::Relaxed);
+						}
+						_ => {}
+					}
+					Ok(())
+				})
 			},
 			..Default::default()
 		})
@@ -102,8 +169,47 @@ pub async fn init_bot(config: Arc<Config>, mm: Arc<MaxMind>) -> Result<(), Error
         .await;
 
     let mut client = client?;
-    client.start().await?;
-    Ok(())
+    let shard_manager = Arc::clone(&client.shard_manager);
+
+    let result = tokio::select! {
+        result = client.start() => result.map_err(Error::from),
+        () = shutdown.cancelled() => {
+            info!("closing Discord shards");
+            shard_manager.shutdown_all().await;
+            Ok(())
+        }
+    };
+    CONNECTED.store(false, Ordering::Relaxed);
+    result
+}
+
+/// Set by pre_command, the span ends once poise drops the invocation
+struct Invocation {
+    started: Instant,
+    span: Span,
+}
+
+/// The command's span, outbound requests instrumented with it are part of the command's trace
+pub async fn span(ctx: &Context<'_>) -> Span {
+    ctx.invocation_data::<Invocation>()
+        .await
+        .map(|invocation| invocation.span.clone())
+        .unwrap_or_else(Span::none)
+}
+
+/// Counts a finished command, its latency is measured from pre_command
+async fn record_command(ctx: Context<'_>, outcome: &'static str) {
+    let started = ctx.invocation_data::<Invocation>().await.map(|invocation| {
+        if outcome == "error" {
+            invocation.span.record("otel.status_code", "ERROR");
+        }
+        invocation.started
+    });
+    crate::prometheus::record_discord_command(
+        &ctx.command().qualified_name,
+        outcome,
+        started.map(|started| started.elapsed()),
+    );
 }
 
 pub async fn attach(ctx: &Context<'_>, text: String, filename: impl Into<String>) {
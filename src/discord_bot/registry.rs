@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use poise::serenity_prelude::ResolvedValue;
+use tracing::Instrument;
+
+use crate::{
+    commands::{Frontend, Invocation, Registry},
+    discord_bot::{Context, Data, Error, reply_or_attach, span},
+    webserver::render::{RenderFormat, RenderOptions, color::ColorDepth},
+};
+
+/// The registry's commands which make sense on Discord, as slash commands
+///
+/// Pages aren't registered as they change with the content, slash commands only when the
+/// bot starts.
+pub fn commands(registry: &Arc<Registry>) -> Vec<poise::Command<Data, Error>> {
+    registry
+        .commands(Frontend::Discord, None)
+        .iter()
+        .map(|command| {
+            let mut slash = registered();
+            slash.name = command.name().into();
+            slash.qualified_name = command.name().into();
+            slash.identifying_name = command.name().into();
+            slash.description = Some(command.help().into());
+            slash.parameters = command
+                .args()
+                .iter()
+                .map(|arg| {
+                    // CommandParameter is only Clone if Data is
+                    let mut parameter = registered().parameters.remove(0);
+                    parameter.name = arg.name.into();
+                    parameter.description = Some(arg.help.into());
+                    parameter.required = arg.required;
+                    parameter
+                })
+                .collect();
+            slash.custom_data = Box::new(Arc::clone(registry));
+            slash
+        })
+        .collect()
+}
+
+/// Runs the registry's command of the same name
+///
+/// `commands` replaces name, description and parameters, `_value` is only the template for
+/// the command's arguments and never set.
+#[poise::command(
+    slash_command,
+    install_context = "Guild|User",
+    interaction_context = "Guild|BotDm|PrivateChannel"
+)]
+async fn registered(ctx: Context<'_>, _value: Option<String>) -> Result<(), Error> {
+    let poise::Context::Application(application) = ctx else {
+        return Ok(());
+    };
+    let Some(registry) = ctx.command().custom_data.downcast_ref::<Arc<Registry>>() else {
+        return Ok(());
+    };
+    let name = &ctx.command().name;
+    let Some(command) = registry.find(name, Frontend::Discord, None) else {
+        return Ok(());
+    };
+
+    ctx.defer().await?;
+
+    // options come in the order they were filled in
+    let mut args = Vec::new();
+    for arg in command.args() {
+        let value = application
+            .args
+            .iter()
+            .find(|option| option.name == arg.name)
+            .and_then(|option| match option.value {
+                ResolvedValue::String(value) => Some(value),
+                _ => None,
+            });
+        match value {
+            Some(value) if arg.rest => args.extend(value.split_whitespace()),
+            Some(value) => args.push(value),
+            None => {}
+        }
+    }
+
+    let invocation = Invocation {
+        frontend: Frontend::Discord,
+        args: &args,
+        client: None,
+        role: None,
+        // Discord's ansi code blocks only know the 8 basic colors and don't wrap
+        render: RenderOptions {
+            format: RenderFormat::Ansi,
+            columns: None,
+            colors: ColorDepth::Bit4,
+            ..Default::default()
+        },
+    };
+    let output = registry
+        .run(name, &invocation)
+        .instrument(span(&ctx).await)
+        .await
+        .unwrap_or_default();
+
+    let filetype = match command.format() {
+        Some(RenderFormat::Plain) => "txt",
+        _ => "ansi",
+    };
+    reply_or_attach(&ctx, output, name.clone(), filetype).await;
+    Ok(())
+}
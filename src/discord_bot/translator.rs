@@ -1,4 +1,5 @@
-use crate::discord_bot::{Context, Error, reply_or_attach};
+use crate::discord_bot::{Context, Error, reply_or_attach, span};
+use tracing::Instrument;
 
 #[poise::command(
     slash_command,
@@ -16,7 +17,7 @@ pub async fn detect(
         return Ok(());
     };
 
-    match translator.detect(text).await {
+    match translator.detect(text).instrument(span(&ctx).await).await {
         Ok(res) => {
             let summary = res
                 .iter()
@@ -48,7 +49,7 @@ pub async fn languages(ctx: Context<'_>) -> Result<(), Error> {
         return Ok(());
     };
 
-    match translator.languages().await {
+    match translator.languages().instrument(span(&ctx).await).await {
         Ok(res) => {
             reply_or_attach(
                 &ctx,
@@ -88,7 +89,11 @@ pub async fn translate(
     let mut source = source.unwrap_or("auto".to_string());
     let target = target.unwrap_or("de".to_string());
 
-    match translator.translate(&source, &target, &text).await {
+    match translator
+        .translate(&source, &target, &text)
+        .instrument(span(&ctx).await)
+        .await
+    {
         Ok(res) => {
             if let Some(det) = &res.detected_language {
                 source = det.language.clone();
@@ -1,4 +1,5 @@
-use crate::discord_bot::{Context, Error, reply_or_attach};
+use crate::discord_bot::{Context, Error, reply_or_attach, span};
+use tracing::Instrument;
 
 #[poise::command(
     slash_command,
@@ -8,7 +9,13 @@ use crate::discord_bot::{Context, Error, reply_or_attach};
 pub async fn wolframalpha(ctx: Context<'_>, expression: String) -> Result<(), Error> {
     ctx.defer().await?;
 
-    let res = match ctx.data().wolframalpha.query(expression).await {
+    let res = match ctx
+        .data()
+        .wolframalpha
+        .query(expression)
+        .instrument(span(&ctx).await)
+        .await
+    {
         Ok(v) => v,
         Err(e) => {
             reply_or_attach(&ctx, e.to_string(), "error", "txt").await;
@@ -126,21 +126,24 @@ impl CATAAS {
     }
 
     async fn fetch_tags(&self) -> anyhow::Result<Vec<String>> {
-        let tags: Vec<String> = self
-            .client
-            .get("https://cataas.com/api/tags")
-            .send()
-            .await?
-            .json::<Vec<String>>()
-            .await?
-            .into_iter()
-            .filter(|tag| !tag.trim().is_empty())
-            .collect();
+        let tags: Vec<String> = crate::prometheus::send_upstream(
+            "cataas",
+            self.client.get("https://cataas.com/api/tags"),
+        )
+        .await?
+        .json::<Vec<String>>()
+        .await?
+        .into_iter()
+        .filter(|tag| !tag.trim().is_empty())
+        .collect();
         Ok(tags)
     }
 
     pub async fn get_image(&self, url: &str) -> anyhow::Result<Bytes> {
-        let res = self.client.get(url).send().await?.bytes().await?;
+        let res = crate::prometheus::send_upstream("cataas", self.client.get(url))
+            .await?
+            .bytes()
+            .await?;
         Ok(res)
     }
 
@@ -166,7 +169,7 @@ impl CATAAS {
         let query = serde_qs::to_string(req)?;
         url.set_query(Some(&query));
 
-        let resp = self.client.get(url).send().await?;
+        let resp = crate::prometheus::send_upstream("cataas", self.client.get(url)).await?;
         if resp.status() == StatusCode::NOT_FOUND {
             return Ok(None);
         }
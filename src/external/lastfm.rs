@@ -7,9 +7,13 @@ use anyhow::Context;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
-use crate::config::types::LastFMConfig;
+use crate::{
+    alert::{self, Severity},
+    config::{SharedConfig, types::LastFMConfig},
+};
 
 const INTERVAL_DEFAULT: u8 = 10;
 const BASE_URL: &str = "http://ws.audioscrobbler.com/2.0/";
@@ -18,7 +22,6 @@ pub const ARTIST_BASE: &str = "https://www.last.fm/music/";
 #[derive(Debug, Clone)]
 pub struct LastFM {
     client: reqwest::Client,
-    interval_secs: u8,
     username: String,
     token: String,
 
@@ -74,7 +77,6 @@ impl LastFM {
 
             return Some(Self {
                 client: client.unwrap_or_default(),
-                interval_secs: lastfm.interval_secs.unwrap_or(INTERVAL_DEFAULT),
                 username,
                 token,
                 cache: Arc::new(Mutex::new(Cache {
@@ -89,20 +91,39 @@ impl LastFM {
         unreachable!()
     }
 
-    pub async fn run_cacher(self: Arc<Self>) {
-        tokio::spawn(async move {
-            loop {
-                let now = Instant::now();
-                if let Err(e) = self.refresh_cache().await {
-                    error!("Failed to refresh Last.fm cache: {:?}", e);
+    /// The interval is read from the config on every run, so it follows config reloads
+    /// Refreshes the cache until `shutdown` is cancelled
+    pub async fn run_cacher(
+        self: Arc<Self>,
+        config: SharedConfig,
+        shutdown: CancellationToken,
+    ) -> anyhow::Result<()> {
+        loop {
+            let now = Instant::now();
+            tokio::select! {
+                result = self.refresh_cache() => {
+                    if let Err(e) = result {
+                        alert::notify(
+                            Severity::Warning,
+                            "LastFM",
+                            format!("Failed to refresh Last.fm cache: {e:#}"),
+                        );
+                    }
                 }
-                let elapsed_ms = now.elapsed().as_millis();
+                () = shutdown.cancelled() => return Ok(()),
+            }
+            let elapsed_ms = now.elapsed().as_millis();
 
-                crate::prometheus::update_lastfm_fetch_duration(elapsed_ms);
+            crate::prometheus::update_lastfm_fetch_duration(elapsed_ms);
 
-                tokio::time::sleep(Duration::from_secs(self.interval_secs.into())).await;
+            let interval_secs = config.load().lastfm.interval_secs;
+            tokio::select! {
+                () = tokio::time::sleep(Duration::from_secs(
+                    interval_secs.unwrap_or(INTERVAL_DEFAULT).into(),
+                )) => {}
+                () = shutdown.cancelled() => return Ok(()),
             }
-        });
+        }
     }
 
     async fn refresh_cache(&self) -> anyhow::Result<()> {
@@ -111,7 +132,7 @@ impl LastFM {
             BASE_URL, self.username, self.token
         );
 
-        let resp = self.client.get(url).send().await?;
+        let resp = crate::prometheus::send_upstream("lastfm", self.client.get(url)).await?;
         crate::prometheus::update_lastfm_fetch_status(resp.status().as_u16());
         let raw_text = resp.text().await?;
         let parsed: UserGetRecentTracksResponse = serde_json::from_str(&raw_text)
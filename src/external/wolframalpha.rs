@@ -69,13 +69,13 @@ impl WolframAlpha {
 
         let params = serde_qs::to_string(&WolframAlphaRequest::new(query, token.to_string()))?;
 
-        let res = self
-            .client
-            .get(format!("{URL}?{params}"))
-            .send()
-            .await?
-            .text()
-            .await?;
+        let res = crate::prometheus::send_upstream(
+            "wolframalpha",
+            self.client.get(format!("{URL}?{params}")),
+        )
+        .await?
+        .text()
+        .await?;
 
         let parsed: serde_json::Value = serde_json::from_str(&res)?;
         if parsed["queryresult"]["success"] == false {
@@ -0,0 +1,154 @@
+use std::{net::IpAddr, sync::OnceLock, time::Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+mod recorder;
+mod sessions;
+
+pub use recorder::Recorder;
+pub use sessions::{list, path};
+
+static MESSAGES: OnceLock<mpsc::Sender<Message>> = OnceLock::new();
+
+/// A line of a session's JSONL file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Connect {
+        ip: Option<IpAddr>,
+        port: Option<u16>,
+    },
+    Auth {
+        method: String,
+        user: String,
+        password: Option<String>,
+    },
+    Pty {
+        term: String,
+        cols: u32,
+        rows: u32,
+    },
+    // the client's terminal was resized
+    Resize {
+        cols: u32,
+        rows: u32,
+    },
+    // a line entered in the shell
+    Command {
+        line: String,
+    },
+    Exec {
+        command: String,
+    },
+    Disconnect,
+}
+
+enum Message {
+    Event(String, Record),
+    // seconds since the session started, "i", "o" or "r" and the data (asciinema v2)
+    Cast(String, f64, &'static str, String),
+    Close(String),
+}
+
+/// Records one SSH connection, the disconnect is recorded once it's dropped
+#[derive(Debug)]
+pub struct Session {
+    id: String,
+    started: Instant,
+}
+
+impl Session {
+    /// None unless privacy.honeypot is enabled
+    pub fn start(peer: Option<std::net::SocketAddr>) -> Option<Self> {
+        MESSAGES.get()?;
+
+        let session = Self {
+            id: format!(
+                "{}-{:08x}",
+                Utc::now().format("%Y%m%dT%H%M%SZ"),
+                rand::random::<u32>()
+            ),
+            started: Instant::now(),
+        };
+        session.record(Event::Connect {
+            ip: peer.map(|peer| peer.ip().to_canonical()),
+            port: peer.map(|peer| peer.port()),
+        });
+        Some(session)
+    }
+
+    pub fn record(&self, event: Event) {
+        send(Message::Event(
+            self.id.clone(),
+            Record {
+                time: Utc::now(),
+                event,
+            },
+        ));
+    }
+
+    /// Bytes typed by the client
+    pub fn input(&self, data: &[u8]) {
+        self.cast("i", data);
+    }
+
+    /// Bytes sent to the client
+    pub fn output(&self, data: impl AsRef<[u8]>) {
+        self.cast("o", data.as_ref());
+    }
+
+    /// The client's terminal was resized, casts replay it as well
+    pub fn resize(&self, cols: u32, rows: u32) {
+        self.record(Event::Resize { cols, rows });
+        self.cast("r", format!("{cols}x{rows}").as_bytes());
+    }
+
+    fn cast(&self, kind: &'static str, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        send(Message::Cast(
+            self.id.clone(),
+            elapsed,
+            kind,
+            String::from_utf8_lossy(data).into_owned(),
+        ));
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.record(Event::Disconnect);
+        send(Message::Close(self.id.clone()));
+    }
+}
+
+/// Never waits for the recorder, while its queue is full events and output are dropped
+fn send(message: Message) {
+    let Some(messages) = MESSAGES.get() else {
+        return;
+    };
+    match messages.try_send(message) {
+        Ok(()) | Err(TrySendError::Closed(_)) => {}
+        // without it the session's files stay open
+        Err(TrySendError::Full(message @ Message::Close(_))) => {
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                let messages = messages.clone();
+                runtime.spawn(async move {
+                    let _ = messages.send(message).await;
+                });
+            }
+        }
+        Err(TrySendError::Full(_)) => crate::prometheus::inc_honeypot_dropped(),
+    }
+}
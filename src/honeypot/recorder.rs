@@ -0,0 +1,271 @@
+use std::{
+    collections::{HashMap, HashSet, hash_map::Entry},
+    fs::{self, Permissions},
+    io,
+    os::unix::fs::{DirBuilderExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::{Mutex, mpsc},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{
+    config::types::HoneypotConfig,
+    honeypot::{Event, MESSAGES, Message},
+};
+
+/// Sessions without a pty are cast with this size
+const DEFAULT_SIZE: (u32, u32) = (80, 24);
+/// Messages waiting for the recorder, more are dropped until it catches up
+const QUEUE_SIZE: usize = 4096;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The files of a session which is still connected
+struct Files {
+    events: File,
+    cast: Option<File>,
+    started: DateTime<Utc>,
+    size: (u32, u32),
+    term: Option<String>,
+}
+
+/// Writes `<id>.jsonl` and `<id>.cast` for every session into privacy.honeypot.dir
+pub struct Recorder {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_age: Option<Duration>,
+    messages: Mutex<mpsc::Receiver<Message>>,
+}
+
+impl Recorder {
+    /// Creates the directory (owner only) and starts queueing sessions
+    pub fn new(config: &HoneypotConfig) -> anyhow::Result<Self> {
+        let dir = PathBuf::from(&config.dir);
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)?;
+        // mode only applies to new directories
+        fs::set_permissions(&dir, Permissions::from_mode(0o700))?;
+
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        if MESSAGES.set(tx).is_err() {
+            anyhow::bail!("honeypot recorder already initialized");
+        }
+        info!("honeypot: recording SSH sessions to {}", config.dir);
+
+        Ok(Self {
+            dir,
+            max_bytes: config.max_total_mb.saturating_mul(1024 * 1024),
+            max_age: (config.max_age_days > 0)
+                .then(|| Duration::from_secs(config.max_age_days.saturating_mul(24 * 60 * 60))),
+            messages: Mutex::new(rx),
+        })
+    }
+
+    /// Writes queued events until `shutdown` is cancelled
+    pub async fn run(self: Arc<Self>, shutdown: CancellationToken) -> anyhow::Result<()> {
+        let mut messages = self.messages.lock().await;
+        let mut sessions = HashMap::new();
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                Some(message) = messages.recv() => self.handle(&mut sessions, message).await,
+                _ = prune.tick() => self.prune(&sessions).await,
+                () = shutdown.cancelled() => break,
+            }
+        }
+
+        while let Ok(message) = messages.try_recv() {
+            self.handle(&mut sessions, message).await;
+        }
+        for (_, mut files) in sessions.drain() {
+            let _ = files.events.flush().await;
+            if let Some(cast) = files.cast.as_mut() {
+                let _ = cast.flush().await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies privacy.honeypot.max_total_mb and max_age_days, connected sessions are kept
+    async fn prune(&self, sessions: &HashMap<String, Files>) {
+        if self.max_bytes == 0 && self.max_age.is_none() {
+            return;
+        }
+        let connected = sessions.keys().cloned().collect();
+        let (dir, max_bytes, max_age) = (self.dir.clone(), self.max_bytes, self.max_age);
+        match tokio::task::spawn_blocking(move || prune_dir(&dir, max_bytes, max_age, &connected))
+            .await
+        {
+            Ok(Ok(0)) => {}
+            Ok(Ok(deleted)) => info!("honeypot: deleted {deleted} recorded sessions"),
+            Ok(Err(e)) => warn!("honeypot: failed to prune {}: {e}", self.dir.display()),
+            Err(e) => warn!("honeypot: pruning panicked: {e}"),
+        }
+    }
+
+    async fn handle(&self, sessions: &mut HashMap<String, Files>, message: Message) {
+        if let Err(e) = self.write(sessions, message).await {
+            warn!("honeypot: failed to record a session: {e}");
+        }
+    }
+
+    async fn write(
+        &self,
+        sessions: &mut HashMap<String, Files>,
+        message: Message,
+    ) -> io::Result<()> {
+        match message {
+            Message::Event(id, record) => {
+                let files = match sessions.entry(id) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let events =
+                            create(&self.dir.join(format!("{}.jsonl", entry.key()))).await?;
+                        entry.insert(Files {
+                            events,
+                            cast: None,
+                            started: record.time,
+                            size: DEFAULT_SIZE,
+                            term: None,
+                        })
+                    }
+                };
+
+                if let Event::Pty { term, cols, rows } = &record.event {
+                    // clients without a terminal send 0x0
+                    if *cols > 0 && *rows > 0 {
+                        files.size = (*cols, *rows);
+                    }
+                    files.term = Some(term.clone());
+                }
+                // the header has the size the cast starts with
+                if let Event::Resize { cols, rows } = record.event
+                    && files.cast.is_none()
+                {
+                    files.size = (cols, rows);
+                }
+
+                let mut line = serde_json::to_string(&record)?;
+                line.push('\n');
+                files.events.write_all(line.as_bytes()).await
+            }
+            Message::Cast(id, elapsed, kind, data) => {
+                // the connect event always comes first
+                let Some(files) = sessions.get_mut(&id) else {
+                    return Ok(());
+                };
+
+                let cast = match &mut files.cast {
+                    Some(cast) => cast,
+                    None => {
+                        let mut cast = create(&self.dir.join(format!("{id}.cast"))).await?;
+                        let header = json!({
+                            "version": 2,
+                            "width": files.size.0,
+                            "height": files.size.1,
+                            "timestamp": files.started.timestamp(),
+                            "env": { "TERM": files.term },
+                        });
+                        cast.write_all(format!("{header}\n").as_bytes()).await?;
+                        files.cast.insert(cast)
+                    }
+                };
+
+                let line = serde_json::to_string(&(elapsed, kind, data))?;
+                cast.write_all(format!("{line}\n").as_bytes()).await
+            }
+            Message::Close(id) => {
+                if let Some(mut files) = sessions.remove(&id) {
+                    files.events.flush().await?;
+                    if let Some(cast) = files.cast.as_mut() {
+                        cast.flush().await?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns how many sessions were deleted, their .jsonl and .cast go together
+fn prune_dir(
+    dir: &Path,
+    max_bytes: u64,
+    max_age: Option<Duration>,
+    connected: &HashSet<String>,
+) -> io::Result<usize> {
+    // the files of each session, their size and when they were written to last
+    let mut recorded: HashMap<String, (Vec<PathBuf>, u64, SystemTime)> = HashMap::new();
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        let Some(id) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| {
+                name.strip_suffix(".jsonl")
+                    .or_else(|| name.strip_suffix(".cast"))
+            })
+        else {
+            continue;
+        };
+        let Some(metadata) = entry.metadata().ok().filter(fs::Metadata::is_file) else {
+            continue;
+        };
+        let modified = metadata.modified()?;
+
+        let session = recorded
+            .entry(id.to_string())
+            .or_insert_with(|| (Vec::new(), 0, modified));
+        session.1 += metadata.len();
+        session.2 = session.2.max(modified);
+        session.0.push(path);
+    }
+
+    let mut recorded: Vec<_> = recorded.into_iter().collect();
+    // newest first
+    recorded.sort_by_key(|(_, (_, _, modified))| std::cmp::Reverse(*modified));
+
+    let now = SystemTime::now();
+    let mut total = 0;
+    let mut deleted = 0;
+    for (id, (paths, size, modified)) in recorded {
+        total += size;
+        if connected.contains(&id) {
+            continue;
+        }
+        let too_old = max_age
+            .is_some_and(|max_age| now.duration_since(modified).is_ok_and(|age| age > max_age));
+        if too_old || (max_bytes > 0 && total > max_bytes) {
+            for path in paths {
+                fs::remove_file(path)?;
+            }
+            total -= size;
+            deleted += 1;
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Readable by the owner only, the events contain passwords
+async fn create(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)
+        .await
+}
@@ -0,0 +1,96 @@
+use std::{
+    io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    honeypot::{Event, Record},
+    maxmind::LookupResponse,
+};
+
+/// A recorded session as listed by the admin endpoint
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub id: String,
+    pub started: Option<DateTime<Utc>>,
+    // None while the session is still connected
+    pub ended: Option<DateTime<Utc>>,
+    pub ip: Option<IpAddr>,
+    pub users: Vec<String>,
+    pub auth_attempts: usize,
+    pub commands: usize,
+    pub cast: bool,
+    // filled in by the endpoint, the lookup uses the current databases
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maxmind: Option<LookupResponse>,
+}
+
+/// All sessions in `dir`, newest first
+pub async fn list(dir: &Path) -> io::Result<Vec<Summary>> {
+    let mut sessions = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let Some(id) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".jsonl"))
+        else {
+            continue;
+        };
+
+        let events = tokio::fs::read_to_string(&path).await?;
+        sessions.push(summarize(id, &events, path.with_extension("cast").exists()));
+    }
+
+    sessions.sort_by(|a, b| b.started.cmp(&a.started).then_with(|| b.id.cmp(&a.id)));
+    Ok(sessions)
+}
+
+fn summarize(id: &str, events: &str, cast: bool) -> Summary {
+    let mut summary = Summary {
+        id: id.into(),
+        started: None,
+        ended: None,
+        ip: None,
+        users: Vec::new(),
+        auth_attempts: 0,
+        commands: 0,
+        cast,
+        maxmind: None,
+    };
+
+    // a line may be cut off while the session is still being written
+    for record in events
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Record>(line).ok())
+    {
+        match record.event {
+            Event::Connect { ip, .. } => {
+                summary.started = Some(record.time);
+                summary.ip = ip;
+            }
+            Event::Auth { user, .. } => {
+                summary.auth_attempts += 1;
+                if !summary.users.contains(&user) {
+                    summary.users.push(user);
+                }
+            }
+            Event::Command { .. } | Event::Exec { .. } => summary.commands += 1,
+            Event::Disconnect => summary.ended = Some(record.time),
+            Event::Pty { .. } | Event::Resize { .. } => {}
+        }
+    }
+
+    summary
+}
+
+/// The file of session `id` with `extension`, None for ids which aren't session ids
+pub fn path(dir: &Path, id: &str, extension: &str) -> Option<PathBuf> {
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then(|| dir.join(format!("{id}.{extension}")))
+}
@@ -0,0 +1,221 @@
+use std::{
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use socket2::{Domain, Socket, Type};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, UnixListener},
+    sync::mpsc,
+};
+use tracing::{debug, info, warn};
+
+use crate::{listener::tls::Tls, redact};
+
+pub mod tls;
+
+/// Unix socket peers have no address, they are local processes (usually the reverse proxy)
+pub const UNIX_PEER: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+/// Connections accepted (and through the TLS handshake) but not picked up by the server yet
+const QUEUE: usize = 128;
+const BACKLOG: i32 = 1024;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where a server listens: `0.0.0.0:3000`, `[::]:3000` or `unix:/run/kybe-backend/web.sock`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl FromStr for ListenAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("unix:") {
+            Some("") => Err("unix: needs a socket path".into()),
+            Some(path) => Ok(Self::Unix(path.into())),
+            None => s
+                .parse()
+                .map(Self::Tcp)
+                .map_err(|_| format!("{s:?} is not ip:port, [ipv6]:port or unix:/path")),
+        }
+    }
+}
+
+impl fmt::Display for ListenAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{addr}"),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> Stream for T {}
+
+/// A TCP, Unix or TLS connection and the peer address
+pub type Connection = (Box<dyn Stream>, SocketAddr);
+
+enum Bound {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+/// A bound address, connections are accepted in the background and handed out by `accept`
+pub struct Listener {
+    address: ListenAddress,
+    connections: mpsc::Receiver<Connection>,
+}
+
+impl Listener {
+    pub fn bind(address: ListenAddress, tls: Option<Tls>) -> io::Result<Self> {
+        let socket = match &address {
+            ListenAddress::Tcp(addr) => Bound::Tcp(bind_tcp(*addr)?),
+            ListenAddress::Unix(path) => {
+                // a socket left over from the last run
+                remove_socket(path)?;
+                Bound::Unix(UnixListener::bind(path)?)
+            }
+        };
+
+        info!(
+            "listening on {address}{}",
+            if tls.is_some() { " (TLS)" } else { "" }
+        );
+
+        let (tx, connections) = mpsc::channel(QUEUE);
+        tokio::spawn(accept_loop(address.clone(), socket, tls, tx));
+
+        Ok(Self {
+            address,
+            connections,
+        })
+    }
+
+    pub async fn accept(&mut self) -> Connection {
+        match self.connections.recv().await {
+            Some(connection) => connection,
+            // the accept loop only stops when this listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+}
+
+impl axum::serve::Listener for Listener {
+    type Io = Box<dyn Stream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        Listener::accept(self).await
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        match self.address {
+            ListenAddress::Tcp(addr) => Ok(addr),
+            ListenAddress::Unix(_) => Ok(UNIX_PEER),
+        }
+    }
+}
+
+/// IPv6 sockets are v6 only, so `0.0.0.0:port` and `[::]:port` can both be bound for dual-stack
+fn bind_tcp(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(BACKLOG)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// Removes the socket at `path`, anything else there (a mistyped path) is left alone and an error
+fn remove_socket(path: &Path) -> io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and isn't a socket", path.display()),
+        )),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+async fn accept_loop(
+    address: ListenAddress,
+    socket: Bound,
+    tls: Option<Tls>,
+    tx: mpsc::Sender<Connection>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            accepted = socket.accept() => accepted,
+            // the server dropped the listener (shutdown or restart)
+            () = tx.closed() => break,
+        };
+
+        let (stream, peer) = match accepted {
+            Ok(connection) => connection,
+            Err(e) => {
+                // mostly running out of file descriptors, give it a moment
+                warn!("{address}: accept failed: {e}");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let Some(tls) = &tls else {
+            if tx.send((stream, peer)).await.is_err() {
+                break;
+            }
+            continue;
+        };
+
+        // handshake off the accept loop so a slow client doesn't block everyone else
+        let acceptor = tls.acceptor();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = tx.send((Box::new(stream), peer)).await;
+                }
+                Ok(Err(e)) => debug!("{}: TLS handshake failed: {e}", redact::ip(peer.ip())),
+                Err(_) => debug!("{}: TLS handshake timed out", redact::ip(peer.ip())),
+            }
+        });
+    }
+
+    if let ListenAddress::Unix(path) = &address
+        && let Err(e) = remove_socket(path)
+    {
+        warn!("{address}: failed to remove the socket: {e}");
+    }
+    debug!("stopped listening on {address}");
+}
+
+impl Bound {
+    async fn accept(&self) -> io::Result<Connection> {
+        match self {
+            Bound::Tcp(listener) => {
+                let (stream, peer) = listener.accept().await?;
+                let _ = stream.set_nodelay(true);
+                Ok((Box::new(stream), peer))
+            }
+            Bound::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Box::new(stream), UNIX_PEER))
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use arc_swap::ArcSwap;
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        ServerConfig,
+        crypto::aws_lc_rs,
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    },
+};
+use tracing::{error, info};
+
+use crate::config::types::TlsConfig;
+
+/// The certificate and key, swapped out when either file changes
+#[derive(Clone)]
+pub struct Tls(Arc<ArcSwap<ServerConfig>>);
+
+impl Tls {
+    /// Loads the certificate and key and reloads them when they change (or on SIGHUP)
+    pub fn new(config: &TlsConfig) -> anyhow::Result<Self> {
+        let cert = PathBuf::from(&config.cert);
+        let key = PathBuf::from(&config.key);
+
+        let tls = Self(Arc::new(ArcSwap::from_pointee(load(&cert, &key)?)));
+        info!("TLS certificate {} loaded", cert.display());
+
+        for path in [&cert, &key] {
+            let tls = tls.clone();
+            let (cert, key) = (cert.clone(), key.clone());
+            crate::watcher::spawn("tls", path.clone(), move || {
+                let tls = tls.clone();
+                let (cert, key) = (cert.clone(), key.clone());
+                async move {
+                    match load(&cert, &key) {
+                        Ok(config) => {
+                            tls.0.store(Arc::new(config));
+                            info!("TLS certificate {} reloaded", cert.display());
+                        }
+                        Err(e) => {
+                            error!("TLS reload failed, keeping the current certificate: {e}")
+                        }
+                    }
+                }
+            })?;
+        }
+
+        Ok(tls)
+    }
+
+    pub fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(self.0.load_full())
+    }
+}
+
+fn load(cert: &Path, key: &Path) -> anyhow::Result<ServerConfig> {
+    let certs = CertificateDer::pem_file_iter(cert)?.collect::<Result<Vec<_>, _>>()?;
+    let key = PrivateKeyDer::from_pem_file(key)?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(config)
+}
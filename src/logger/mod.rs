@@ -0,0 +1,229 @@
+use std::env;
+use std::fmt;
+use std::io::stdout;
+use std::sync::OnceLock;
+use tracing::subscriber::DefaultGuard;
+use tracing::{Subscriber, error, span};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::{DefaultFields, Format, JsonFields, PrettyFields, Writer};
+use tracing_subscriber::fmt::{FormatFields, FormattedFields, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, Registry, reload};
+
+use crate::config::types::{LogFormat, LogRotation, LoggerConfig};
+
+mod otel;
+mod prune;
+mod recent;
+
+pub use otel::{set_parent, shutdown, trace_headers, trace_id};
+pub use prune::prune;
+pub use recent::recent;
+
+/// Log files are named `kybe_backend.log.<date>`
+const FILE_PREFIX: &str = "kybe_backend.log";
+/// Used until the config is loaded
+const BOOTSTRAP_DIRECTIVES: &str = "info,kybe_backend=debug";
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Creates a default tracing logger that is used as long as DefaultGuard is not dropped
+pub fn init_logger_bootstrap() -> anyhow::Result<DefaultGuard> {
+    let filter = get_logger_filter(BOOTSTRAP_DIRECTIVES);
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_thread_ids(true)
+        .with_file(true)
+        .with_line_number(true)
+        .with_target(true)
+        .with_env_filter(filter)
+        .finish();
+
+    Ok(tracing::subscriber::set_default(subscriber))
+}
+
+/// Takes a old_logger (from init_logger_bootstrap) and creates a new logger which includes file
+/// logging which location we should have from the config and the OTLP exporter if enabled
+///
+/// Drops the old_logger before setting the new logger
+pub fn init_logger(config: &LoggerConfig, old_logger: DefaultGuard) -> anyhow::Result<()> {
+    let (filter, handle) = reload::Layer::new(get_logger_filter(&directives(config)));
+
+    // same default as tracing_subscriber, log files never get colors
+    let color = env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+    let mut layers = vec![
+        fmt_layer(config.format, stdout, color),
+        // what the SSH admin console's `logs` shows
+        recent::layer(),
+    ];
+
+    if config.file_logger_enabled {
+        let mut appender = RollingFileAppender::builder()
+            .rotation(match config.rotation {
+                LogRotation::Minutely => Rotation::MINUTELY,
+                LogRotation::Hourly => Rotation::HOURLY,
+                LogRotation::Daily => Rotation::DAILY,
+                LogRotation::Weekly => Rotation::WEEKLY,
+                LogRotation::Never => Rotation::NEVER,
+            })
+            .filename_prefix(FILE_PREFIX);
+        if config.max_files > 0 {
+            appender = appender.max_log_files(config.max_files);
+        }
+
+        layers.push(file_layer(config.format, appender.build(&config.dir)?));
+    }
+
+    let otlp = if config.otlp.enabled {
+        Some(otel::layer(&config.otlp)?)
+    } else {
+        None
+    };
+
+    // filters the log output only, the OTLP layer keeps its own filter and still gets every span
+    let subscriber = tracing_subscriber::registry()
+        .with(layers.with_filter(filter))
+        .with(otlp);
+
+    drop(old_logger);
+    tracing::subscriber::set_global_default(subscriber)?;
+    let _ = FILTER.set(handle);
+    Ok(())
+}
+
+fn fmt_layer<S, W>(format: LogFormat, writer: W, color: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(color);
+    let event = event_format();
+
+    match format {
+        LogFormat::Full => layer.event_format(otel::TraceIds(event)).boxed(),
+        LogFormat::Pretty => layer
+            .event_format(otel::TraceIds(event.pretty()))
+            .fmt_fields(PrettyFields::new())
+            .boxed(),
+        LogFormat::Compact => layer.event_format(otel::TraceIds(event.compact())).boxed(),
+        LogFormat::Json => layer
+            .event_format(otel::TraceIds(event.json()))
+            .fmt_fields(JsonFields::new())
+            .boxed(),
+    }
+}
+
+/// Same as the stdout layer without colors, its span fields are kept apart from stdout's
+fn file_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(false);
+    let event = event_format();
+
+    match format {
+        LogFormat::Full => layer
+            .event_format(otel::TraceIds(event))
+            .fmt_fields(FileFields(DefaultFields::new()))
+            .boxed(),
+        LogFormat::Pretty => layer
+            .event_format(otel::TraceIds(event.pretty()))
+            .fmt_fields(FileFields(PrettyFields::new()))
+            .boxed(),
+        LogFormat::Compact => layer
+            .event_format(otel::TraceIds(event.compact()))
+            .fmt_fields(FileFields(DefaultFields::new()))
+            .boxed(),
+        LogFormat::Json => layer
+            .event_format(otel::TraceIds(event.json()))
+            .fmt_fields(FileFields(JsonFields::new()))
+            .boxed(),
+    }
+}
+
+fn event_format() -> Format {
+    tracing_subscriber::fmt::format()
+        .with_thread_ids(true)
+        .with_file(true)
+        .with_line_number(true)
+        .with_target(true)
+}
+
+/// The file layer's fields under a type of its own. Spans keep the fields each fmt layer formats
+/// by the formatter's type, shared with stdout the files would get its colors and every field twice
+struct FileFields<F>(F);
+
+impl<'writer, F> FormatFields<'writer> for FileFields<F>
+where
+    F: for<'a> FormatFields<'a>,
+{
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        self.0.format_fields(writer, fields)
+    }
+
+    // JSON merges fields recorded later into the object, the inner formatter has to add them
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &span::Record<'_>,
+    ) -> fmt::Result {
+        let mut inner = FormattedFields::<F>::new(std::mem::take(&mut current.fields));
+        let result = self.0.add_fields(&mut inner, fields);
+        current.fields = inner.fields;
+        result
+    }
+}
+
+/// `logger.level` and `logger.targets` as filter directives (`info,kybe_backend=debug`)
+pub fn directives(config: &LoggerConfig) -> String {
+    std::iter::once(config.level.clone())
+        .chain(
+            config
+                .targets
+                .iter()
+                .map(|(target, level)| format!("{target}={level}")),
+        )
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The active filter, None before init_logger
+pub fn filter() -> Option<String> {
+    FILTER.get()?.with_current(|f| f.to_string()).ok()
+}
+
+/// Replaces the filter until the next restart or reset, `directives` uses the RUST_LOG syntax
+pub fn set_filter(directives: &str) -> anyhow::Result<String> {
+    reload_filter(EnvFilter::try_new(directives)?)
+}
+
+/// Goes back to the filter from RUST_LOG or the config
+pub fn reset_filter(config: &LoggerConfig) -> anyhow::Result<String> {
+    reload_filter(get_logger_filter(&directives(config)))
+}
+
+fn reload_filter(filter: EnvFilter) -> anyhow::Result<String> {
+    let handle = FILTER
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("logger isn't initialized"))?;
+    handle.reload(filter)?;
+    Ok(handle.with_current(|f| f.to_string())?)
+}
+
+/// RUST_LOG if it's set, `directives` otherwise
+fn get_logger_filter(directives: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| match EnvFilter::try_new(directives) {
+        Ok(filter) => filter,
+        Err(e) => {
+            error!("failed to parse directives {directives:?}: {e}");
+            EnvFilter::new("info")
+        }
+    })
+}
@@ -0,0 +1,190 @@
+use std::{fmt, sync::OnceLock, time::Duration};
+
+use axum::http::HeaderMap;
+use opentelemetry::{
+    global,
+    trace::{SpanId, TraceContextExt, TraceId, TracerProvider},
+};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    Resource,
+    propagation::TraceContextPropagator,
+    trace::{Sampler, SdkTracerProvider},
+};
+use tracing::{Event, Level, Span, Subscriber};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    Layer,
+    filter::Targets,
+    fmt::{
+        FmtContext, FormatEvent, FormatFields,
+        format::{Compact, Format, Full, Json, Pretty, Writer},
+    },
+    registry::LookupSpan,
+};
+
+use crate::config::types::OtlpConfig;
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Exports the spans of this crate to an OTLP/HTTP collector
+///
+/// Also installs the W3C trace context propagator, so `traceparent` headers are only
+/// read and sent while exporting is enabled.
+pub(super) fn layer<S>(config: &OtlpConfig) -> anyhow::Result<impl Layer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(&config.endpoint)
+        .with_timeout(Duration::from_secs(config.timeout_secs))
+        .build()?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        // traces started by a client keep the client's sampling decision
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .with_attribute(opentelemetry::KeyValue::new(
+                    "service.version",
+                    crate::GIT_SHA.clone(),
+                ))
+                .build(),
+        )
+        .build();
+
+    let tracer = provider.tracer("kybe-backend");
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let _ = PROVIDER.set(provider);
+
+    // spans of dependencies (hyper, russh, the exporter's own requests) aren't exported
+    Ok(tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(Targets::new().with_target("kybe_backend", Level::TRACE)))
+}
+
+/// Exports the spans which are still buffered, called once before exiting
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        eprintln!("failed to flush traces: {e}");
+    }
+}
+
+/// Continues the trace of a `traceparent` header, requests without one start a new trace
+pub fn set_parent(span: &Span, headers: &HeaderMap) {
+    let parent = global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(headers)));
+    if parent.span().span_context().is_valid() {
+        let _ = span.set_parent(parent);
+    }
+}
+
+/// `traceparent` headers making `span` the parent of an outbound request
+pub fn trace_headers(span: &Span) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let context = span.context();
+    global::get_text_map_propagator(|p| {
+        p.inject_context(&context, &mut HeaderInjector(&mut headers))
+    });
+    headers
+}
+
+/// The trace id of `span`, None unless it's exported
+pub fn trace_id(span: &Span) -> Option<String> {
+    let context = span.context();
+    let span = context.span();
+    let span_context = span.span_context();
+    span_context
+        .is_sampled()
+        .then(|| span_context.trace_id().to_string())
+}
+
+/// Adds `trace_id` and `span_id` of the event's span to log lines, so they can be found
+/// in the exported traces. Lines outside of an exported span are left as they are.
+#[derive(Clone)]
+pub struct TraceIds<L, T>(pub Format<L, T>);
+
+/// Where a format puts the ids, `event` is the formatted event including its newline
+pub trait WithIds {
+    fn with_ids(event: &str, trace_id: TraceId, span_id: SpanId) -> String;
+}
+
+impl WithIds for Full {
+    fn with_ids(event: &str, trace_id: TraceId, span_id: SpanId) -> String {
+        let event = event.trim_end_matches('\n');
+        format!("{event} trace_id={trace_id} span_id={span_id}\n")
+    }
+}
+
+impl WithIds for Compact {
+    fn with_ids(event: &str, trace_id: TraceId, span_id: SpanId) -> String {
+        Full::with_ids(event, trace_id, span_id)
+    }
+}
+
+impl WithIds for Pretty {
+    // an extra line before the blank line ending the event
+    fn with_ids(event: &str, trace_id: TraceId, span_id: SpanId) -> String {
+        let event = event.trim_end_matches('\n');
+        format!("{event}\n    trace_id={trace_id} span_id={span_id}\n\n")
+    }
+}
+
+impl WithIds for Json {
+    fn with_ids(event: &str, trace_id: TraceId, span_id: SpanId) -> String {
+        match event.trim_end().strip_suffix('}') {
+            Some(object) => {
+                format!("{object},\"trace_id\":\"{trace_id}\",\"span_id\":\"{span_id}\"}}\n")
+            }
+            None => event.to_string(),
+        }
+    }
+}
+
+impl<S, N, L, T> FormatEvent<S, N> for TraceIds<L, T>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    L: WithIds,
+    Format<L, T>: FormatEvent<S, N> + Clone,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        // the global dispatcher, which holds the OpenTelemetry layer if exporting is enabled
+        let ids = ctx.parent_span().and_then(|span| {
+            tracing::dispatcher::get_default(|dispatch| {
+                tracing_opentelemetry::get_otel_context(&span.id(), dispatch)
+            })
+        });
+        let ids = ids.and_then(|context| {
+            let span_context = context.span().span_context().clone();
+            span_context
+                .is_sampled()
+                .then(|| (span_context.trace_id(), span_context.span_id()))
+        });
+
+        let Some((trace_id, span_id)) = ids else {
+            return self.0.format_event(ctx, writer, event);
+        };
+
+        // formatted into a buffer first, as the event ends with a newline
+        let mut line = String::new();
+        self.0
+            .clone()
+            .with_ansi(writer.has_ansi_escapes())
+            .format_event(ctx, Writer::new(&mut line), event)?;
+
+        writer.write_str(&L::with_ids(&line, trace_id, span_id))
+    }
+}
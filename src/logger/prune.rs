@@ -0,0 +1,62 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{config::types::LoggerConfig, logger::FILE_PREFIX};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Deletes the oldest log files while all of them together are larger than `logger.max_total_mb`,
+/// runs as a supervised subsystem
+pub async fn prune(config: LoggerConfig, shutdown: CancellationToken) -> anyhow::Result<()> {
+    let max_bytes = config.max_total_mb.saturating_mul(1024 * 1024);
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = shutdown.cancelled() => return Ok(()),
+        }
+
+        let dir = PathBuf::from(&config.dir);
+        match tokio::task::spawn_blocking(move || prune_dir(&dir, max_bytes)).await? {
+            Ok(0) => {}
+            Ok(deleted) => info!(
+                "deleted {deleted} log files, {} is limited to {} MiB",
+                config.dir, config.max_total_mb
+            ),
+            Err(e) => warn!("failed to prune {}: {e}", config.dir),
+        }
+    }
+}
+
+/// Returns how many files were deleted, the newest file is being written to and always kept
+fn prune_dir(dir: &Path, max_bytes: u64) -> io::Result<usize> {
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(FILE_PREFIX))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(fs::Metadata::is_file)?;
+            Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+        })
+        .collect();
+    // newest first
+    files.sort_by_key(|(_, _, modified)| std::cmp::Reverse(*modified));
+
+    let mut total = 0;
+    let mut deleted = 0;
+    for (i, (path, size, _)) in files.iter().enumerate() {
+        total += size;
+        if i > 0 && total > max_bytes {
+            fs::remove_file(path)?;
+            deleted += 1;
+        }
+    }
+
+    Ok(deleted)
+}
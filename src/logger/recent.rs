@@ -0,0 +1,111 @@
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    sync::{LazyLock, Mutex},
+};
+
+use tracing::Subscriber;
+use tracing_subscriber::{
+    Layer,
+    field::RecordFields,
+    fmt::{
+        FormatFields, MakeWriter,
+        format::{DefaultFields, Writer as FieldWriter},
+    },
+    registry::LookupSpan,
+};
+
+use crate::logger::otel;
+
+/// Lines kept for the SSH admin console's `logs`
+const CAPACITY: usize = 1000;
+
+static LINES: LazyLock<Mutex<VecDeque<String>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(CAPACITY)));
+
+/// Keeps the last log lines in memory, independent of file logging
+#[derive(Clone, Copy, Debug)]
+pub struct Recent;
+
+/// The default fields under another type, spans keep the fields each fmt layer formats
+/// separately, shared ones would get the stdout layer's colors and every field twice
+#[derive(Default)]
+pub struct Fields(DefaultFields);
+
+impl<'writer> FormatFields<'writer> for Fields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        writer: FieldWriter<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        self.0.format_fields(writer, fields)
+    }
+}
+
+/// Buffers one event, the lines are stored once it's written completely
+pub struct Writer(Vec<u8>);
+
+impl<'a> MakeWriter<'a> for Recent {
+    type Writer = Writer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Writer(Vec::new())
+    }
+}
+
+impl io::Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let text = String::from_utf8_lossy(&self.0);
+        let Ok(mut lines) = LINES.lock() else {
+            return;
+        };
+        for line in text.lines().filter(|line| !line.is_empty()) {
+            if lines.len() == CAPACITY {
+                lines.pop_front();
+            }
+            lines.push_back(line.to_string());
+        }
+    }
+}
+
+/// Compact lines without colors into the buffer
+pub fn layer<S>() -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let event = tracing_subscriber::fmt::format()
+        .with_thread_ids(true)
+        .with_file(true)
+        .with_line_number(true)
+        .with_target(true)
+        .compact();
+    tracing_subscriber::fmt::layer()
+        .with_writer(Recent)
+        .with_ansi(false)
+        .fmt_fields(Fields::default())
+        .event_format(otel::TraceIds(event))
+        .boxed()
+}
+
+/// The last `n` log lines, oldest first
+pub fn recent(n: usize) -> Vec<String> {
+    let Ok(lines) = LINES.lock() else {
+        return Vec::new();
+    };
+    lines
+        .iter()
+        .skip(lines.len().saturating_sub(n))
+        .cloned()
+        .collect()
+}
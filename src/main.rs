@@ -1,20 +1,31 @@
 #![warn(clippy::unwrap_used)]
 
+pub mod alert;
 pub mod external;
 pub mod maxmind;
 pub mod prometheus;
 pub mod translator;
 
+mod commands;
 mod config;
 mod discord_bot;
+mod honeypot;
+mod listener;
 mod logger;
+mod redact;
 mod ssh;
+mod supervisor;
+mod watcher;
 mod webserver;
 
+use crate::alert::Dispatcher;
+use crate::config::SharedConfig;
 use crate::config::types::Config;
 use crate::external::lastfm::LastFM;
 use crate::maxmind::MaxMind;
-use futures::future::try_join_all;
+use crate::supervisor::Supervisor;
+use crate::webserver::content::{self, ContentPages, SharedPages};
+use arc_swap::ArcSwap;
 use once_cell::sync::Lazy;
 use std::env;
 use std::sync::Arc;
@@ -23,64 +34,108 @@ use tracing::warn;
 pub static GIT_SHA: Lazy<String> =
     Lazy::new(|| env::var("KYBE_BACKEND_GIT_SHA").unwrap_or("dev".to_string()));
 
-pub async fn notify_error(title: impl AsRef<str>, msg: impl Into<String>, exit: bool) {
-    tracing::error!("{}: {}", title.as_ref(), msg.into());
-
-    if exit {
-        std::process::exit(1)
-    }
-}
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Init logger in two phases:
     // 1. without file logging to print info about loading the config
     // 2. with file logging derived from the config persistent for the rest of the application
     let bootstrap_guard = logger::init_logger_bootstrap()?;
-    let config = Arc::new(Config::init().await?);
-    logger::init_logger(&config.logger, bootstrap_guard)?;
+    let config: SharedConfig = Arc::new(ArcSwap::from_pointee(Config::init().await?));
+    logger::init_logger(&config.load().logger, bootstrap_guard)?;
+    redact::configure(&config.load().privacy);
+
+    config::reload::spawn_watcher(Arc::clone(&config))?;
 
     prometheus::register_custom_metrics();
 
-    let mut handles = Vec::new();
+    let mut supervisor = Supervisor::new();
+
+    // started first and stopped last so failures of the other subsystems are delivered,
+    // including the ones while they shut down
+    let alerts = Arc::new(Dispatcher::new(&config.load())?);
+    supervisor.spawn_last("Alerts", move |shutdown| Arc::clone(&alerts).run(shutdown));
 
-    let mm = Arc::new(MaxMind::new(config.maxmind.clone())?);
-    let lastfm = if config.lastfm.enable {
-        let lastfm = LastFM::new(&config.lastfm).map(Arc::new);
-        if let Some(ref lastfm) = lastfm {
-            Arc::clone(lastfm).run_cacher().await;
+    if config.load().privacy.honeypot.enabled {
+        let recorder = Arc::new(honeypot::Recorder::new(&config.load().privacy.honeypot)?);
+        supervisor.spawn("Honeypot", false, move |shutdown| {
+            Arc::clone(&recorder).run(shutdown)
+        });
+    }
+
+    let logger_config = config.load().logger.clone();
+    if logger_config.file_logger_enabled && logger_config.max_total_mb > 0 {
+        supervisor.spawn("Log pruning", false, move |shutdown| {
+            logger::prune(logger_config.clone(), shutdown)
+        });
+    }
+
+    let assets_dir = content::assets_dir();
+    let pages: SharedPages = Arc::new(ArcSwap::from_pointee(ContentPages::load(&assets_dir)?));
+    content::spawn_watcher(Arc::clone(&pages), assets_dir)?;
+
+    let mm = Arc::new(MaxMind::new(config.load().maxmind.clone())?);
+    let lastfm = if config.load().lastfm.enable {
+        let lastfm = LastFM::new(&config.load().lastfm).map(Arc::new);
+        if let Some(lastfm) = lastfm.clone() {
+            let config = Arc::clone(&config);
+            supervisor.spawn("LastFM", false, move |shutdown| {
+                Arc::clone(&lastfm).run_cacher(Arc::clone(&config), shutdown)
+            });
         }
         lastfm
     } else {
         None
     };
 
-    if config.discord_bot.enable {
-        let config = Arc::clone(&config);
+    // the pages and a few commands, shown over SSH and (some) as slash commands
+    let site = Arc::new(commands::Site {
+        config: Arc::clone(&config),
+        pages: Arc::clone(&pages),
+        lastfm: lastfm.clone(),
+    });
+    let commands = Arc::new(commands::Registry::new(
+        Arc::clone(&config),
+        site,
+        Arc::clone(&mm),
+    ));
+
+    if config.load().discord_bot.enable {
+        // the bot only reads its config at startup
+        let config = config.load_full();
         let mm = Arc::clone(&mm);
+        let commands = Arc::clone(&commands);
 
-        handles.push(tokio::spawn(async move {
-            if let Err(e) = discord_bot::init_bot(config, mm).await {
-                notify_error("Discord Bot", format!("init failed: {e}",), true).await;
-            }
-        }));
+        supervisor.spawn("Discord Bot", false, move |shutdown| {
+            discord_bot::init_bot(
+                Arc::clone(&config),
+                Arc::clone(&mm),
+                Arc::clone(&commands),
+                shutdown,
+            )
+        });
     }
 
     {
-        let config = Arc::clone(&config);
-        handles.push(tokio::spawn(async move {
-            if let Err(e) = ssh::init(Arc::clone(&config)).await {
-                notify_error("SSH", format!("init failed: {e}"), true).await;
-            };
-        }));
+        // the server itself only reads its config at startup
+        let config = config.load_full();
+        supervisor.spawn("SSH", false, move |shutdown| {
+            ssh::init(Arc::clone(&config), Arc::clone(&commands), shutdown)
+        });
     }
 
-    handles.push(tokio::spawn(async move {
-        if let Err(e) = webserver::init_webserver(config, mm, lastfm).await {
-            notify_error("Discord Bot", format!("init failed: {e}"), true).await;
-        }
-    }));
+    let health = supervisor.health();
+    supervisor.spawn("Webserver", true, move |shutdown| {
+        webserver::init_webserver(
+            Arc::clone(&config),
+            Arc::clone(&mm),
+            lastfm.clone(),
+            Arc::clone(&pages),
+            health.clone(),
+            shutdown,
+        )
+    });
 
-    try_join_all(handles).await?;
-    Ok(())
+    let result = supervisor.run().await;
+    logger::shutdown();
+    result
 }
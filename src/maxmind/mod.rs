@@ -57,20 +57,45 @@ impl MaxMind {
         Ok(Self { city, asn })
     }
 
+    /// Unix time the City database was built at, None if it isn't loaded
+    pub fn city_build_epoch(&self) -> Option<u64> {
+        self.city.as_ref().map(|c| c.metadata().build_epoch)
+    }
+
+    /// Unix time the ASN database was built at, None if it isn't loaded
+    pub fn asn_build_epoch(&self) -> Option<u64> {
+        self.asn.as_ref().map(|a| a.metadata().build_epoch)
+    }
+
     pub fn lookup(&self, ip: IpAddr) -> anyhow::Result<LookupResponse> {
+        let city = self
+            .city
+            .as_ref()
+            .map(|c| c.lookup(ip)?.decode::<CityMin>())
+            .transpose();
+        let asn = self
+            .asn
+            .as_ref()
+            .map(|a| a.lookup(ip)?.decode::<AsnMin>())
+            .transpose();
+
+        record_lookup("city", &city);
+        record_lookup("asn", &asn);
+
         Ok(LookupResponse {
-            city: self
-                .city
-                .as_ref()
-                .map(|c| c.lookup(ip)?.decode::<CityMin>())
-                .transpose()?
-                .flatten(),
-            asn: self
-                .asn
-                .as_ref()
-                .map(|a| a.lookup(ip)?.decode::<AsnMin>())
-                .transpose()?
-                .flatten(),
+            city: city?.flatten(),
+            asn: asn?.flatten(),
         })
     }
 }
+
+/// Counts a lookup as hit, miss or error, nothing is counted when the database isn't loaded
+fn record_lookup<T, E>(database: &'static str, result: &Result<Option<Option<T>>, E>) {
+    let result = match result {
+        Ok(None) => return,
+        Ok(Some(Some(_))) => "hit",
+        Ok(Some(None)) => "miss",
+        Err(_) => "error",
+    };
+    crate::prometheus::inc_maxmind_lookup(database, result);
+}
@@ -1,11 +1,20 @@
 use anyhow::anyhow;
 use prometheus_client::{
     encoding::{EncodeLabelSet, text::encode},
-    metrics::{counter::Counter, family::Family, gauge::Gauge, histogram::Histogram},
+    metrics::{
+        counter::Counter,
+        family::Family,
+        gauge::Gauge,
+        histogram::{Histogram, exponential_buckets},
+    },
     registry::Registry,
 };
-use std::sync::{LazyLock, Mutex};
+use std::{
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
 use tokio::time::Instant;
+use tracing::{Instrument, field::Empty, info_span};
 
 const BASE: &str = "kybe_backend_";
 
@@ -14,6 +23,71 @@ pub struct StatusLabel {
     pub status: u16,
 }
 
+/// `route` is always a matched path (or `unmatched`), never the raw request path
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct RouteLabels {
+    pub method: &'static str,
+    pub route: String,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct HttpStatusLabels {
+    pub method: &'static str,
+    pub route: String,
+    // 2xx, 4xx, ...
+    pub status: &'static str,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct RenderLabels {
+    pub route: String,
+    pub format: &'static str,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct CommandLabels {
+    pub command: String,
+    // ok or error
+    pub outcome: &'static str,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct CommandNameLabels {
+    pub command: String,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct UpstreamLabels {
+    pub service: &'static str,
+    // the HTTP status code, or error when there was no response
+    pub status: String,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct ServiceLabels {
+    pub service: &'static str,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct SshAuthLabels {
+    pub method: &'static str,
+    pub result: &'static str,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct SshCommandLabels {
+    // exec or shell
+    pub mode: &'static str,
+    pub command: &'static str,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct MaxMindLabels {
+    pub database: &'static str,
+    // hit, miss or error
+    pub result: &'static str,
+}
+
 pub static REGISTRY: LazyLock<Mutex<Registry>> =
     LazyLock::new(|| Mutex::new(<Registry>::default()));
 pub static START_TIME: LazyLock<Instant> = LazyLock::new(Instant::now);
@@ -28,6 +102,53 @@ pub static LASTFM_FETCH_STATUS: LazyLock<Family<StatusLabel, Counter>> =
 pub static LASTFM_LISTENING_STATE: LazyLock<Gauge> = LazyLock::new(Gauge::default);
 pub static LASTFM_FETCH_TIMESTAMP: LazyLock<Gauge> = LazyLock::new(Gauge::default);
 
+pub static HTTP_REQUESTS: LazyLock<Family<HttpStatusLabels, Counter>> =
+    LazyLock::new(Family::default);
+type HistogramFamily<S> = Family<S, Histogram, fn() -> Histogram>;
+
+pub static HTTP_REQUEST_DURATION: LazyLock<HistogramFamily<RouteLabels>> =
+    LazyLock::new(|| Family::new_with_constructor(request_duration_histogram));
+pub static HTTP_RESPONSE_SIZE: LazyLock<HistogramFamily<RouteLabels>> =
+    LazyLock::new(|| Family::new_with_constructor(response_size_histogram));
+pub static HTTP_REQUESTS_IN_FLIGHT: LazyLock<Gauge> = LazyLock::new(Gauge::default);
+pub static HTTP_RATE_LIMITED: LazyLock<Family<RouteLabels, Counter>> =
+    LazyLock::new(Family::default);
+pub static HTTP_RENDERS: LazyLock<Family<RenderLabels, Counter>> = LazyLock::new(Family::default);
+
+pub static DISCORD_COMMANDS: LazyLock<Family<CommandLabels, Counter>> =
+    LazyLock::new(Family::default);
+pub static DISCORD_COMMAND_DURATION: LazyLock<HistogramFamily<CommandNameLabels>> =
+    LazyLock::new(|| Family::new_with_constructor(command_duration_histogram));
+pub static UPSTREAM_REQUESTS: LazyLock<Family<UpstreamLabels, Counter>> =
+    LazyLock::new(Family::default);
+pub static UPSTREAM_REQUEST_DURATION: LazyLock<HistogramFamily<ServiceLabels>> =
+    LazyLock::new(|| Family::new_with_constructor(request_duration_histogram));
+pub static SSH_CONNECTIONS: LazyLock<Counter> = LazyLock::new(Counter::default);
+pub static SSH_SESSIONS: LazyLock<Gauge> = LazyLock::new(Gauge::default);
+pub static SSH_AUTH_ATTEMPTS: LazyLock<Family<SshAuthLabels, Counter>> =
+    LazyLock::new(Family::default);
+pub static SSH_COMMANDS: LazyLock<Family<SshCommandLabels, Counter>> =
+    LazyLock::new(Family::default);
+pub static MAXMIND_LOOKUPS: LazyLock<Family<MaxMindLabels, Counter>> =
+    LazyLock::new(Family::default);
+pub static HONEYPOT_DROPPED: LazyLock<Counter> = LazyLock::new(Counter::default);
+
+fn request_duration_histogram() -> Histogram {
+    Histogram::new(vec![
+        0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+    ])
+}
+
+// commands defer and wait on upstream APIs, so they take a lot longer than HTTP requests
+fn command_duration_histogram() -> Histogram {
+    Histogram::new(vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0])
+}
+
+fn response_size_histogram() -> Histogram {
+    // 128 B to 2 MiB
+    Histogram::new(exponential_buckets(128.0, 4.0, 8))
+}
+
 pub fn register_custom_metrics() {
     let mut reg = REGISTRY.lock().expect("Mutex lock shouldn't fail");
     reg.register(
@@ -55,6 +176,86 @@ pub fn register_custom_metrics() {
         "Unix timestamp of the last Last.fm sync event (seconds since epoch)",
         LASTFM_FETCH_TIMESTAMP.clone(),
     );
+    reg.register(
+        format!("{BASE}http_requests"),
+        "HTTP requests by matched route and status class",
+        HTTP_REQUESTS.clone(),
+    );
+    reg.register(
+        format!("{BASE}http_request_duration_seconds"),
+        "HTTP request latency by matched route",
+        HTTP_REQUEST_DURATION.clone(),
+    );
+    reg.register(
+        format!("{BASE}http_response_size_bytes"),
+        "HTTP response body size by matched route (only when known up front)",
+        HTTP_RESPONSE_SIZE.clone(),
+    );
+    reg.register(
+        format!("{BASE}http_requests_in_flight"),
+        "HTTP requests currently being handled",
+        HTTP_REQUESTS_IN_FLIGHT.clone(),
+    );
+    reg.register(
+        format!("{BASE}http_rate_limited"),
+        "HTTP requests rejected by the rate limiter",
+        HTTP_RATE_LIMITED.clone(),
+    );
+    reg.register(
+        format!("{BASE}http_renders"),
+        "Pages rendered by matched route and output format",
+        HTTP_RENDERS.clone(),
+    );
+    reg.register(
+        format!("{BASE}discord_commands"),
+        "Discord slash command invocations by command and outcome",
+        DISCORD_COMMANDS.clone(),
+    );
+    reg.register(
+        format!("{BASE}discord_command_duration_seconds"),
+        "Discord slash command latency",
+        DISCORD_COMMAND_DURATION.clone(),
+    );
+    reg.register(
+        format!("{BASE}upstream_requests"),
+        "Requests to external APIs by service and status",
+        UPSTREAM_REQUESTS.clone(),
+    );
+    reg.register(
+        format!("{BASE}upstream_request_duration_seconds"),
+        "Latency of external APIs until the response headers arrived",
+        UPSTREAM_REQUEST_DURATION.clone(),
+    );
+    reg.register(
+        format!("{BASE}ssh_connections"),
+        "Accepted SSH connections",
+        SSH_CONNECTIONS.clone(),
+    );
+    reg.register(
+        format!("{BASE}ssh_sessions"),
+        "Currently connected SSH clients",
+        SSH_SESSIONS.clone(),
+    );
+    reg.register(
+        format!("{BASE}ssh_auth_attempts"),
+        "SSH authentication attempts by method and result",
+        SSH_AUTH_ATTEMPTS.clone(),
+    );
+    reg.register(
+        format!("{BASE}ssh_commands"),
+        "SSH commands executed by mode and command",
+        SSH_COMMANDS.clone(),
+    );
+    reg.register(
+        format!("{BASE}maxmind_lookups"),
+        "MaxMind lookups by database and result",
+        MAXMIND_LOOKUPS.clone(),
+    );
+    reg.register(
+        format!("{BASE}honeypot_dropped"),
+        "Honeypot events and output dropped because the recorder fell behind",
+        HONEYPOT_DROPPED.clone(),
+    );
 }
 
 pub fn update_lastfm_fetch_duration(duration_ms: u128) {
@@ -76,6 +277,176 @@ pub fn set_listening_state(is_listening: bool) {
     LASTFM_LISTENING_STATE.set(if is_listening { 1 } else { 0 });
 }
 
+/// Counts a request as in flight until dropped, so cancelled requests are removed too
+pub struct InFlight(());
+
+impl InFlight {
+    pub fn start() -> Self {
+        HTTP_REQUESTS_IN_FLIGHT.inc();
+        Self(())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        HTTP_REQUESTS_IN_FLIGHT.dec();
+    }
+}
+
+pub fn record_http_request(
+    labels: &RouteLabels,
+    status: u16,
+    duration: Duration,
+    response_size: Option<u64>,
+) {
+    let status = match status {
+        100..200 => "1xx",
+        200..300 => "2xx",
+        300..400 => "3xx",
+        400..500 => "4xx",
+        _ => "5xx",
+    };
+
+    HTTP_REQUESTS
+        .get_or_create(&HttpStatusLabels {
+            method: labels.method,
+            route: labels.route.clone(),
+            status,
+        })
+        .inc();
+    HTTP_REQUEST_DURATION
+        .get_or_create(labels)
+        .observe(duration.as_secs_f64());
+    if let Some(size) = response_size {
+        HTTP_RESPONSE_SIZE
+            .get_or_create(labels)
+            .observe(size as f64);
+    }
+}
+
+pub fn inc_http_rate_limited(labels: &RouteLabels) {
+    HTTP_RATE_LIMITED.get_or_create(labels).inc();
+}
+
+pub fn inc_http_render(route: &str, format: &'static str) {
+    HTTP_RENDERS
+        .get_or_create(&RenderLabels {
+            route: route.into(),
+            format,
+        })
+        .inc();
+}
+
+pub fn record_discord_command(command: &str, outcome: &'static str, duration: Option<Duration>) {
+    DISCORD_COMMANDS
+        .get_or_create(&CommandLabels {
+            command: command.into(),
+            outcome,
+        })
+        .inc();
+    if let Some(duration) = duration {
+        DISCORD_COMMAND_DURATION
+            .get_or_create(&CommandNameLabels {
+                command: command.into(),
+            })
+            .observe(duration.as_secs_f64());
+    }
+}
+
+/// Sends `request` in a client span and records its status and latency as `service`
+pub async fn send_upstream(
+    service: &'static str,
+    request: reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+
+    // only the host, query strings can contain api keys
+    let span = info_span!(
+        "upstream_request",
+        otel.name = format!("{} {service}", request.method()),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        peer.service = service,
+        http.request.method = %request.method(),
+        server.address = request.url().host_str().unwrap_or_default(),
+        http.response.status_code = Empty,
+    );
+    request
+        .headers_mut()
+        .extend(crate::logger::trace_headers(&span));
+
+    let started = Instant::now();
+    let result = client.execute(request).instrument(span.clone()).await;
+
+    match &result {
+        Ok(response) => {
+            span.record("http.response.status_code", response.status().as_u16());
+            if response.status().is_server_error() {
+                span.record("otel.status_code", "ERROR");
+            }
+        }
+        Err(_) => {
+            span.record("otel.status_code", "ERROR");
+        }
+    }
+
+    UPSTREAM_REQUEST_DURATION
+        .get_or_create(&ServiceLabels { service })
+        .observe(started.elapsed().as_secs_f64());
+    UPSTREAM_REQUESTS
+        .get_or_create(&UpstreamLabels {
+            service,
+            status: match &result {
+                Ok(response) => response.status().as_u16().to_string(),
+                Err(_) => "error".into(),
+            },
+        })
+        .inc();
+
+    result
+}
+
+/// Counts a connected SSH client until dropped
+#[derive(Debug)]
+pub struct SshSession(());
+
+impl SshSession {
+    pub fn start() -> Self {
+        SSH_CONNECTIONS.inc();
+        SSH_SESSIONS.inc();
+        Self(())
+    }
+}
+
+impl Drop for SshSession {
+    fn drop(&mut self) {
+        SSH_SESSIONS.dec();
+    }
+}
+
+pub fn inc_ssh_auth(method: &'static str, result: &'static str) {
+    SSH_AUTH_ATTEMPTS
+        .get_or_create(&SshAuthLabels { method, result })
+        .inc();
+}
+
+pub fn inc_honeypot_dropped() {
+    HONEYPOT_DROPPED.inc();
+}
+
+pub fn inc_ssh_command(mode: &'static str, command: &'static str) {
+    SSH_COMMANDS
+        .get_or_create(&SshCommandLabels { mode, command })
+        .inc();
+}
+
+pub fn inc_maxmind_lookup(database: &'static str, result: &'static str) {
+    MAXMIND_LOOKUPS
+        .get_or_create(&MaxMindLabels { database, result })
+        .inc();
+}
+
 pub fn export_metrics() -> anyhow::Result<String> {
     let uptime = START_TIME.elapsed().as_secs() as i64;
     UPTIME_SECONDS.set(uptime);
@@ -0,0 +1,108 @@
+use std::{
+    borrow::Cow,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, LazyLock},
+};
+
+use arc_swap::ArcSwap;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+
+use crate::config::types::{PrivacyConfig, PrivacyMode};
+
+/// Logged in place of passwords
+pub const MASK: &str = "<redacted>";
+
+struct Policy {
+    mode: PrivacyMode,
+    key: Vec<u8>,
+}
+
+// until configure is called nothing is logged in the clear
+static POLICY: LazyLock<ArcSwap<Policy>> = LazyLock::new(|| {
+    ArcSwap::from_pointee(Policy {
+        mode: PrivacyMode::Hash,
+        key: random_key(),
+    })
+});
+
+// the key used while privacy.hash_key is empty, kept across reloads
+static RANDOM_KEY: LazyLock<Vec<u8>> = LazyLock::new(random_key);
+
+fn random_key() -> Vec<u8> {
+    rand::random::<[u8; 32]>().to_vec()
+}
+
+/// Applies `privacy.mode` and `privacy.hash_key`, called at startup and on config reloads
+pub fn configure(config: &PrivacyConfig) {
+    let key = if config.hash_key.is_empty() {
+        RANDOM_KEY.clone()
+    } else {
+        config.hash_key.as_bytes().to_vec()
+    };
+    POLICY.store(Arc::new(Policy {
+        mode: config.mode,
+        key,
+    }));
+}
+
+/// A client IP as the privacy mode allows it to be logged
+pub struct Ip(Option<IpAddr>);
+
+pub fn ip(ip: impl Into<Option<IpAddr>>) -> Ip {
+    Ip(ip.into())
+}
+
+impl fmt::Display for Ip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(ip) = self.0.map(|ip| ip.to_canonical()) else {
+            return f.write_str("unknown");
+        };
+
+        let policy = POLICY.load();
+        match policy.mode {
+            PrivacyMode::Off => write!(f, "{ip}"),
+            PrivacyMode::Truncate => match ip {
+                IpAddr::V4(ip) => {
+                    let [a, b, c, _] = ip.octets();
+                    write!(f, "{}/24", Ipv4Addr::new(a, b, c, 0))
+                }
+                IpAddr::V6(ip) => {
+                    let [a, b, c, ..] = ip.segments();
+                    write!(f, "{}/48", Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+                }
+            },
+            PrivacyMode::Hash => f.write_str(&hash(&policy.key, ip.to_string().as_bytes())),
+        }
+    }
+}
+
+/// Ids of users (Discord user ids), only logged as they are with privacy.mode = "off"
+pub fn id(id: impl fmt::Display) -> String {
+    let policy = POLICY.load();
+    match policy.mode {
+        PrivacyMode::Off => id.to_string(),
+        PrivacyMode::Truncate | PrivacyMode::Hash => hash(&policy.key, id.to_string().as_bytes()),
+    }
+}
+
+/// Free text typed by clients (commands, arguments), which may contain anything including secrets
+pub fn text(text: &str) -> Cow<'_, str> {
+    match POLICY.load().mode {
+        PrivacyMode::Off => Cow::Borrowed(text),
+        PrivacyMode::Truncate | PrivacyMode::Hash => {
+            Cow::Owned(format!("<{} chars>", text.chars().count()))
+        }
+    }
+}
+
+/// The first 8 bytes of a HMAC-SHA256, enough to tell clients apart in the logs
+fn hash(key: &[u8], value: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(value);
+    mac.finalize().into_bytes()[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
@@ -0,0 +1,87 @@
+use std::{io, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use tracing::warn;
+
+use crate::{commands::Frontend, redact, ssh::keys::Login};
+
+/// ssh.audit_log, one JSON line per login with a key and per admin command
+#[derive(Debug)]
+pub struct Audit {
+    path: PathBuf,
+}
+
+/// What an authorized key did
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Login,
+    Command,
+    // the command needs a role the key doesn't have
+    Denied,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry<'a> {
+    time: DateTime<Utc>,
+    action: Action,
+    // the user and IP as privacy.mode allows them to be logged
+    user: String,
+    key: &'a str,
+    name: &'a str,
+    role: &'static str,
+    ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frontend: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    args: &'a [&'a str],
+}
+
+impl Audit {
+    pub fn new(path: &str) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Appends an entry, a failed write is logged and doesn't stop the command
+    pub async fn record(
+        &self,
+        login: &Login,
+        ip: String,
+        action: Action,
+        command: Option<(Frontend, &str, &[&str])>,
+    ) {
+        let entry = Entry {
+            time: Utc::now(),
+            action,
+            user: redact::id(&login.user),
+            key: &login.fingerprint,
+            name: &login.name,
+            role: login.role.as_str(),
+            ip,
+            frontend: command.map(|(frontend, ..)| frontend.as_str()),
+            command: command.map(|(_, command, _)| command),
+            args: command.map(|(.., args)| args).unwrap_or_default(),
+        };
+
+        if let Err(e) = self.write(&entry).await {
+            warn!("failed to write ssh.audit_log: {e}");
+        }
+    }
+
+    async fn write(&self, entry: &Entry<'_>) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        // readable by the owner only, like the honeypot's recordings
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await
+    }
+}
@@ -0,0 +1,438 @@
+use unicode_width::UnicodeWidthChar;
+
+pub const PROMPT: &str = "> ";
+
+/// Lines kept for up/down per shell
+const HISTORY_SIZE: usize = 100;
+
+/// Turned on with the shell's first prompt, terminals then wrap pasted text in
+/// `ESC [200~` and `ESC [201~` so newlines in it don't submit the line
+pub const BRACKETED_PASTE_ON: &str = "\x1b[?2004h";
+pub const BRACKETED_PASTE_OFF: &str = "\x1b[?2004l";
+
+/// What a byte of input finished
+pub enum Input {
+    None,
+    Line(String),
+    // Ctrl-C
+    Interrupt,
+    // Ctrl-D on an empty line
+    Eof,
+}
+
+/// The line being typed in a shell, with its history
+#[derive(Clone, Debug, Default)]
+pub struct LineEditor {
+    buffer: Vec<char>,
+    // index into buffer, not a column
+    cursor: usize,
+    history: Vec<String>,
+    // the history entry shown, None while editing a new line
+    browsing: Option<usize>,
+    // the new line while browsing the history
+    draft: Vec<char>,
+    escape: Vec<u8>,
+    // an incomplete UTF-8 character
+    utf8: Vec<u8>,
+    pasting: bool,
+    // \r\n only submits once
+    after_cr: bool,
+}
+
+impl LineEditor {
+    /// Handles one byte of input, `complete` gets the words before the one being completed and
+    /// returns the candidates for it
+    pub fn feed(
+        &mut self,
+        byte: u8,
+        output: &mut Vec<String>,
+        complete: impl Fn(&[&str]) -> Vec<String>,
+    ) -> Input {
+        let after_cr = std::mem::take(&mut self.after_cr);
+
+        if byte == 0x1b || !self.escape.is_empty() {
+            self.escape.push(byte);
+            self.escape(output);
+            return Input::None;
+        }
+
+        if byte >= 0x80 || !self.utf8.is_empty() {
+            self.utf8(byte, output);
+            return Input::None;
+        }
+
+        if self.pasting {
+            match byte {
+                b'\r' | b'\n' | b'\t' => self.insert(' ', output),
+                byte if byte.is_ascii_graphic() || byte == b' ' => {
+                    self.insert(byte as char, output)
+                }
+                _ => {}
+            }
+            return Input::None;
+        }
+
+        match byte {
+            b'\n' if after_cr => {}
+            b'\r' | b'\n' => {
+                self.after_cr = byte == b'\r';
+                return self.submit(output);
+            }
+            // Ctrl-A
+            1 => self.move_to(0, output),
+            // Ctrl-B
+            2 => self.move_to(self.cursor.saturating_sub(1), output),
+            // Ctrl-C
+            3 => return Input::Interrupt,
+            // Ctrl-D
+            4 if self.buffer.is_empty() => return Input::Eof,
+            4 => self.delete(self.cursor, self.cursor + 1, output),
+            // Ctrl-E
+            5 => self.move_to(self.buffer.len(), output),
+            // Ctrl-F
+            6 => self.move_to(self.cursor + 1, output),
+            b'\t' => self.complete(output, complete),
+            // Ctrl-K
+            11 => self.delete(self.cursor, self.buffer.len(), output),
+            // Ctrl-L
+            12 => {
+                output.push("\x1b[2J\x1b[H".into());
+                self.redraw(output);
+            }
+            // Ctrl-N
+            14 => self.history_next(output),
+            // Ctrl-P
+            16 => self.history_previous(output),
+            // Ctrl-U
+            21 => self.delete(0, self.cursor, output),
+            // Ctrl-W
+            23 => self.delete(self.word_start(), self.cursor, output),
+            // Backspace, Ctrl-H
+            8 | 127 => self.delete(self.cursor.saturating_sub(1), self.cursor, output),
+            byte if byte.is_ascii_graphic() || byte == b' ' => self.insert(byte as char, output),
+            _ => {}
+        }
+
+        Input::None
+    }
+
+    /// Draws the prompt and the line, leaving the terminal's cursor at the line's cursor
+    pub fn redraw(&self, output: &mut Vec<String>) {
+        output.push("\r\x1b[2K".into());
+        output.push(PROMPT.into());
+        output.push(self.buffer.iter().collect());
+
+        let right = width(&self.buffer[self.cursor..]);
+        if right > 0 {
+            output.push(format!("\x1b[{right}D"));
+        }
+    }
+
+    fn submit(&mut self, output: &mut Vec<String>) -> Input {
+        let line = self.buffer.iter().collect::<String>();
+        self.buffer.clear();
+        self.cursor = 0;
+        self.browsing = None;
+        self.draft.clear();
+        output.push("\r\n".into());
+
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && self.history.last().is_none_or(|last| last != trimmed) {
+            if self.history.len() == HISTORY_SIZE {
+                self.history.remove(0);
+            }
+            self.history.push(trimmed.into());
+        }
+
+        Input::Line(trimmed.into())
+    }
+
+    fn insert(&mut self, c: char, output: &mut Vec<String>) {
+        self.buffer.insert(self.cursor, c);
+        self.cursor += 1;
+        // pasted text is drawn once the paste ends
+        if !self.pasting {
+            self.redraw(output);
+        }
+    }
+
+    /// Removes `start..end` (clamped to the line), the cursor ends up at `start`
+    fn delete(&mut self, start: usize, end: usize, output: &mut Vec<String>) {
+        let end = end.min(self.buffer.len());
+        if start >= end {
+            return;
+        }
+        self.buffer.drain(start..end);
+        self.cursor = start;
+        self.redraw(output);
+    }
+
+    fn move_to(&mut self, cursor: usize, output: &mut Vec<String>) {
+        let cursor = cursor.min(self.buffer.len());
+        if cursor != self.cursor {
+            self.cursor = cursor;
+            self.redraw(output);
+        }
+    }
+
+    /// Start of the word before the cursor, spaces before the cursor are skipped
+    fn word_start(&self) -> usize {
+        let mut i = self.cursor;
+        while i > 0 && self.buffer[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !self.buffer[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// End of the word after the cursor
+    fn word_end(&self) -> usize {
+        let mut i = self.cursor;
+        while i < self.buffer.len() && self.buffer[i].is_whitespace() {
+            i += 1;
+        }
+        while i < self.buffer.len() && !self.buffer[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    fn history_previous(&mut self, output: &mut Vec<String>) {
+        let index = match self.browsing {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.buffer.clone();
+                self.history.len() - 1
+            }
+        };
+        self.browsing = Some(index);
+        self.show(self.history[index].chars().collect(), output);
+    }
+
+    fn history_next(&mut self, output: &mut Vec<String>) {
+        let Some(index) = self.browsing else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.browsing = Some(index + 1);
+            self.show(self.history[index + 1].chars().collect(), output);
+        } else {
+            self.browsing = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.show(draft, output);
+        }
+    }
+
+    fn show(&mut self, line: Vec<char>, output: &mut Vec<String>) {
+        self.buffer = line;
+        self.cursor = self.buffer.len();
+        self.redraw(output);
+    }
+
+    /// Completes the word before the cursor, lists the candidates if there is more than one
+    fn complete(&mut self, output: &mut Vec<String>, complete: impl Fn(&[&str]) -> Vec<String>) {
+        let start = self.word_start();
+        // the cursor is after a space, a new word is started
+        let start = if self.cursor > 0 && self.buffer[self.cursor - 1].is_whitespace() {
+            self.cursor
+        } else {
+            start
+        };
+
+        let before = self.buffer[..start].iter().collect::<String>();
+        let words = before.split_whitespace().collect::<Vec<_>>();
+        let partial = self.buffer[start..self.cursor].iter().collect::<String>();
+
+        let candidates = complete(&words)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(&partial))
+            .collect::<Vec<_>>();
+
+        let completion = match candidates.as_slice() {
+            [] => return,
+            [candidate] => format!("{candidate} "),
+            candidates => common_prefix(candidates),
+        };
+
+        if completion.len() > partial.len() {
+            for c in completion[partial.len()..].chars() {
+                self.buffer.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            self.redraw(output);
+        } else if candidates.len() > 1 {
+            output.push(format!("\r\n{}\r\n", candidates.join("  ")));
+            self.redraw(output);
+        }
+    }
+
+    fn utf8(&mut self, byte: u8, output: &mut Vec<String>) {
+        self.utf8.push(byte);
+        let expected = match self.utf8[0] {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            // a continuation byte without a start
+            _ => {
+                self.utf8.clear();
+                return;
+            }
+        };
+        if self.utf8.len() < expected {
+            return;
+        }
+
+        let bytes = std::mem::take(&mut self.utf8);
+        if let Ok(text) = std::str::from_utf8(&bytes) {
+            text.chars()
+                .filter(|c| !c.is_control())
+                .for_each(|c| self.insert(c, output));
+        }
+    }
+
+    /// Waits for a whole CSI (`ESC [ ... final`), SS3 (`ESC O x`) or Alt (`ESC x`) sequence
+    fn escape(&mut self, output: &mut Vec<String>) {
+        let sequence = match self.escape.as_slice() {
+            [0x1b] => return,
+            [0x1b, b'[' | b'O'] => return,
+            [0x1b, b'[', .., last] if !(0x40..=0x7e).contains(last) && self.escape.len() < 16 => {
+                return;
+            }
+            _ => std::mem::take(&mut self.escape),
+        };
+
+        match &sequence[1..] {
+            b"[200~" => self.pasting = true,
+            b"[201~" => {
+                self.pasting = false;
+                self.redraw(output);
+            }
+            // everything but the end of a paste is inserted as it is
+            _ if self.pasting => {}
+            b"[D" | b"OD" => self.move_to(self.cursor.saturating_sub(1), output),
+            b"[C" | b"OC" => self.move_to(self.cursor + 1, output),
+            b"[A" | b"OA" => self.history_previous(output),
+            b"[B" | b"OB" => self.history_next(output),
+            b"[H" | b"OH" | b"[1~" | b"[7~" => self.move_to(0, output),
+            b"[F" | b"OF" | b"[4~" | b"[8~" => self.move_to(self.buffer.len(), output),
+            b"[3~" => self.delete(self.cursor, self.cursor + 1, output),
+            // Alt-b, Ctrl-Left
+            b"b" | b"[1;5D" | b"[1;3D" => self.move_to(self.word_start(), output),
+            // Alt-f, Ctrl-Right
+            b"f" | b"[1;5C" | b"[1;3C" => self.move_to(self.word_end(), output),
+            // Alt-d
+            b"d" => self.delete(self.cursor, self.word_end(), output),
+            // Alt-Backspace
+            b"\x7f" => self.delete(self.word_start(), self.cursor, output),
+            _ => {}
+        }
+    }
+}
+
+/// Columns `chars` take up in a terminal, wide characters (CJK, emoji) take two
+fn width(chars: &[char]) -> usize {
+    chars.iter().map(|c| c.width().unwrap_or(0)).sum()
+}
+
+fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix = candidates[0].clone();
+    for candidate in &candidates[1..] {
+        let len = prefix
+            .chars()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum();
+        prefix.truncate(len);
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `bytes` one at a time, returns the submitted lines
+    fn feed(editor: &mut LineEditor, bytes: &[u8]) -> Vec<String> {
+        let mut output = Vec::new();
+        bytes
+            .iter()
+            .filter_map(
+                |&byte| match editor.feed(byte, &mut output, |_| Vec::new()) {
+                    Input::Line(line) => Some(line),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    fn buffer(editor: &LineEditor) -> String {
+        editor.buffer.iter().collect()
+    }
+
+    #[test]
+    fn crlf_submits_once() {
+        let mut editor = LineEditor::default();
+        assert_eq!(feed(&mut editor, b"ls\r\n"), ["ls"]);
+        assert_eq!(feed(&mut editor, b"help\r\n\r\n"), ["help", ""]);
+        assert_eq!(feed(&mut editor, b"a\nb\r"), ["a", "b"]);
+    }
+
+    #[test]
+    fn lf_after_cr_only_skips_right_after() {
+        let mut editor = LineEditor::default();
+        assert_eq!(feed(&mut editor, b"a\r"), ["a"]);
+        assert_eq!(feed(&mut editor, b"b\n"), ["b"]);
+        assert_eq!(feed(&mut editor, b"\n"), [""]);
+    }
+
+    #[test]
+    fn split_utf8_is_inserted_once_complete() {
+        let mut editor = LineEditor::default();
+        let bytes = "ä€🦀".as_bytes();
+        for (i, &byte) in bytes.iter().enumerate() {
+            feed(&mut editor, &[byte]);
+            let complete = std::str::from_utf8(&bytes[..=i]).is_ok();
+            assert_eq!(editor.utf8.is_empty(), complete);
+        }
+        assert_eq!(buffer(&editor), "ä€🦀");
+        assert_eq!(feed(&mut editor, b"\r"), ["ä€🦀"]);
+    }
+
+    #[test]
+    fn invalid_utf8_is_dropped() {
+        let mut editor = LineEditor::default();
+        // a stray continuation byte, then a start byte cut off by ASCII
+        feed(&mut editor, &[0x80, b'a', 0xe2, 0x82, b'b', b'c']);
+        assert_eq!(buffer(&editor), "ac");
+    }
+
+    #[test]
+    fn pasted_newlines_dont_submit() {
+        let mut editor = LineEditor::default();
+        assert!(feed(&mut editor, b"\x1b[200~echo a\r\nrm -rf /\n\x1b[201~").is_empty());
+        assert_eq!(buffer(&editor), "echo a  rm -rf / ");
+        assert_eq!(feed(&mut editor, b"\r"), ["echo a  rm -rf /"]);
+    }
+
+    #[test]
+    fn pasted_escapes_are_ignored() {
+        let mut editor = LineEditor::default();
+        feed(&mut editor, b"\x1b[200~a\x1b[Db\x03\x1b[201~");
+        assert_eq!(buffer(&editor), "ab");
+        assert!(!editor.pasting);
+    }
+
+    #[test]
+    fn pasted_utf8_is_kept() {
+        let mut editor = LineEditor::default();
+        let mut paste = b"\x1b[200~".to_vec();
+        paste.extend_from_slice("grüße\n".as_bytes());
+        paste.extend_from_slice(b"\x1b[201~\r");
+        assert_eq!(feed(&mut editor, &paste), ["grüße"]);
+    }
+}
@@ -0,0 +1,118 @@
+use std::{fs, io, path::PathBuf, sync::Arc};
+
+use arc_swap::ArcSwap;
+use russh::keys::{
+    PublicKey,
+    ssh_key::{AuthorizedKeys, HashAlg},
+};
+use tracing::{info, warn};
+
+use crate::commands::Role;
+
+/// The keys from ssh.authorized_keys and their roles, reloaded when the file changes
+#[derive(Debug)]
+pub struct Keys {
+    // empty if nobody gets the admin console
+    path: PathBuf,
+    keys: ArcSwap<Vec<(PublicKey, Role)>>,
+}
+
+/// Who logged in with a key from ssh.authorized_keys
+#[derive(Clone, Debug)]
+pub struct Login {
+    pub user: String,
+    // SHA256:…, as `ssh-keygen -l` shows it
+    pub fingerprint: String,
+    // the key's comment in ssh.authorized_keys
+    pub name: String,
+    pub role: Role,
+}
+
+impl Keys {
+    pub fn load(path: &str) -> Self {
+        let keys = Self {
+            path: path.into(),
+            keys: ArcSwap::default(),
+        };
+        keys.reload();
+        keys
+    }
+
+    fn reload(&self) {
+        let keys = self.read();
+        info!(
+            "{} keys in ssh.authorized_keys get the admin console",
+            keys.len()
+        );
+        self.keys.store(Arc::new(keys));
+    }
+
+    /// Entries which can't be parsed are skipped with a warning, without the file there are none
+    fn read(&self) -> Vec<(PublicKey, Role)> {
+        if self.path.as_os_str().is_empty() {
+            return Vec::new();
+        }
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                warn!("failed to read ssh.authorized_keys: {e}");
+                return Vec::new();
+            }
+        };
+
+        AuthorizedKeys::new(&text)
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("ssh.authorized_keys: skipping an invalid entry: {e}");
+                    None
+                }
+            })
+            .filter_map(|entry| {
+                let role = entry
+                    .config_opts()
+                    .iter()
+                    .find_map(|option| option.strip_prefix("role="))
+                    .map(|role| role.trim_matches('"'));
+                let role = match role {
+                    None | Some("viewer") => Role::Viewer,
+                    Some("admin") => Role::Admin,
+                    Some(role) => {
+                        warn!(
+                            "ssh.authorized_keys: skipping {}, unknown role {role:?}",
+                            fingerprint(entry.public_key())
+                        );
+                        return None;
+                    }
+                };
+                Some((entry.public_key().clone(), role))
+            })
+            .collect()
+    }
+
+    /// The entry of `key`, None if it isn't authorized
+    pub fn find(&self, key: &PublicKey) -> Option<(PublicKey, Role)> {
+        // the comment is only a name, the key data identifies the key
+        self.keys
+            .load()
+            .iter()
+            .find(|(authorized, _)| authorized.key_data() == key.key_data())
+            .cloned()
+    }
+}
+
+/// Watches ssh.authorized_keys (and SIGHUP), keys can be added and removed without a restart
+pub fn spawn_watcher(keys: Arc<Keys>) -> anyhow::Result<()> {
+    if keys.path.as_os_str().is_empty() {
+        return Ok(());
+    }
+    crate::watcher::spawn("authorized_keys", keys.path.clone(), move || {
+        let keys = Arc::clone(&keys);
+        async move { keys.reload() }
+    })
+}
+
+pub fn fingerprint(key: &PublicKey) -> String {
+    key.fingerprint(HashAlg::Sha256).to_string()
+}
@@ -1,52 +1,102 @@
-use std::{collections::HashMap, fs, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
 
+use anyhow::anyhow;
+use futures::future::join_all;
 use russh::{
     ChannelId, Pty,
-    keys::PrivateKey,
+    keys::{PrivateKey, PublicKey},
     server::{self, Server as _},
 };
-use tokio::{net::TcpListener, sync::Mutex};
-use tracing::{info, warn};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, Span, debug, field::Empty, info, info_span, warn};
+
+use crate::{
+    commands::{Frontend, Invocation, Registry, Role},
+    config, honeypot,
+    listener::{ListenAddress, Listener},
+    prometheus::{self, SshSession},
+    redact,
+    webserver::{
+        MAX_COLUMNS, MIN_COLUMNS,
+        render::{DEFAULT_COLUMNS, RenderFormat, RenderOptions, color::ColorDepth, strip_escapes},
+    },
+};
+
+mod audit;
+mod editor;
+mod keys;
+mod tui;
 
-use crate::config;
+use audit::{Action as AuditAction, Audit};
+use editor::{BRACKETED_PASTE_OFF, BRACKETED_PASTE_ON, Input, LineEditor};
+use keys::{Keys, Login};
+use tui::{Action, Browser};
 
-const PGP_KEY: &str = include_str!("../../static/pgp.txt");
-const IDENTITY: &str = include_str!("../../static/ident.txt");
+/// Set while all ssh.listen addresses are bound (shown on /health/ready)
+pub static LISTENING: AtomicBool = AtomicBool::new(false);
+
+/// The connected clients, disconnected on shutdown
+type Connections = Arc<Mutex<HashMap<u64, server::Handle>>>;
 
 #[derive(Clone, Debug)]
 struct ClientState {
     #[allow(unused)]
     channel: ChannelId,
-    #[allow(unused)]
-    handle: russh::server::Handle,
-    buffer: String,
-    cursor: usize,
-    escape: Vec<u8>,
+    editor: LineEditor,
     #[allow(unused)]
     ip: Option<std::net::SocketAddr>,
+    // set by pty_request, output then needs \r\n line endings
+    pty: bool,
+    // the pty's size, updated on resizes, 0 without a pty (pages are then as wide as for curl)
+    col_width: u32,
+    row_height: u32,
+    // guessed from the pty's TERM, without a pty output isn't downsampled
+    colors: ColorDepth,
+    // set while the full-screen browser is open, it gets the input instead of the editor
+    browser: Option<Browser>,
 }
 
 impl ClientState {
-    pub fn new(
-        channel: ChannelId,
-        handle: russh::server::Handle,
-        ip: Option<std::net::SocketAddr>,
-    ) -> Self {
+    pub fn new(channel: ChannelId, ip: Option<std::net::SocketAddr>) -> Self {
         Self {
             channel,
-            handle,
-            buffer: String::default(),
-            cursor: usize::default(),
-            escape: Vec::default(),
+            editor: LineEditor::default(),
             ip,
+            pty: false,
+            col_width: 0,
+            row_height: 0,
+            colors: ColorDepth::default(),
+            browser: None,
         }
     }
 }
 
 #[derive(Clone, Debug)]
 struct Server {
-    clients: Arc<Mutex<HashMap<ChannelId, ClientState>>>,
+    // the connection's channels, their ids are only unique within a connection. Handlers run one
+    // at a time, a channel's state is taken out while commands are awaited and put back after
+    clients: HashMap<ChannelId, ClientState>,
     ip: Option<std::net::SocketAddr>,
+    // dropped with the connection's handler
+    session: Option<Arc<SshSession>>,
+    // spans the whole connection, commands are its children
+    span: Span,
+    // set while privacy.honeypot is enabled, dropped with the connection's handler
+    recording: Option<Arc<honeypot::Session>>,
+    commands: Arc<Registry>,
+    keys: Arc<Keys>,
+    audit: Arc<Audit>,
+    // set once the client logged in with a key from ssh.authorized_keys
+    login: Option<Arc<Login>>,
 }
 
 impl server::Server for Server {
@@ -54,7 +104,17 @@ impl server::Server for Server {
 
     fn new_client(&mut self, ip: Option<std::net::SocketAddr>) -> Self::Handler {
         let mut s = self.clone();
+        s.clients = HashMap::new();
         s.ip = ip;
+        s.session = Some(Arc::new(SshSession::start()));
+        s.recording = honeypot::Session::start(ip).map(Arc::new);
+        s.span = info_span!(
+            parent: None,
+            "ssh_session",
+            otel.kind = "server",
+            user = Empty,
+            auth.method = Empty,
+        );
         s
     }
 
@@ -63,6 +123,111 @@ impl server::Server for Server {
     }
 }
 
+impl Server {
+    /// The client's IP as privacy.mode allows it to be logged
+    fn client(&self) -> redact::Ip {
+        redact::ip(self.ip.map(|addr| addr.ip()))
+    }
+
+    /// The role of the key the client logged in with, None for visitors
+    fn role(&self) -> Option<Role> {
+        self.login.as_ref().map(|login| login.role)
+    }
+
+    /// Runs `command` for the channel's terminal, None if there is no such command
+    ///
+    /// `crlf` is set for the shell and a pty, `columns` is the pty's width. Without a pty (0)
+    /// output is as wide as for `curl`. Commands which need a role are audited.
+    async fn run(
+        &self,
+        frontend: Frontend,
+        command: &str,
+        args: &[&str],
+        crlf: bool,
+        columns: u32,
+        colors: ColorDepth,
+    ) -> Option<String> {
+        if let Some(login) = &self.login
+            && let Some(needed) = self
+                .commands
+                .find(command, frontend, Some(Role::Admin))
+                .and_then(|command| command.role())
+        {
+            let action = match needed <= login.role {
+                true => AuditAction::Command,
+                false => AuditAction::Denied,
+            };
+            info!(parent: &self.span, user = %redact::id(&login.user), key = %login.fingerprint, role = login.role.as_str(), action = ?action, command, "admin_command");
+            let ip = self.client().to_string();
+            self.audit
+                .record(login, ip, action, Some((frontend, command, args)))
+                .await;
+        }
+
+        let invocation = Invocation {
+            frontend,
+            args,
+            client: self.ip.map(|addr| addr.ip()),
+            role: self.role(),
+            render: RenderOptions {
+                format: RenderFormat::Ansi,
+                columns: Some(match columns {
+                    0 => DEFAULT_COLUMNS,
+                    columns => (columns as usize).clamp(MIN_COLUMNS, MAX_COLUMNS),
+                }),
+                colors,
+                ..Default::default()
+            },
+        };
+        let output = self.commands.run(command, &invocation).await?;
+        Some(if crlf {
+            output.replace('\n', "\r\n")
+        } else {
+            output
+        })
+    }
+
+    /// Opens the browser on the channel's terminal, None without a pty or if it's too small
+    async fn browse(&self, state: &mut ClientState, exec: bool) -> Option<String> {
+        if !state.pty || state.col_width == 0 || state.row_height < tui::MIN_ROWS {
+            return None;
+        }
+        let mut browser = Browser::new(self.commands.sections(), exec);
+        let section = self.section(browser.section(), state.col_width, state.colors);
+        browser.show(&section.await);
+        let frame = browser.draw(state.col_width, state.row_height);
+        state.browser = Some(browser);
+        Some(format!("{}{frame}", tui::ENTER))
+    }
+
+    /// A section of the browser rendered for `columns` and `colors`
+    async fn section(&self, section: &str, columns: u32, colors: ColorDepth) -> String {
+        self.run(Frontend::Shell, section, &[], false, columns, colors)
+            .await
+            .unwrap_or_default()
+    }
+
+    fn record(&self, event: honeypot::Event) {
+        if let Some(recording) = &self.recording {
+            recording.record(event);
+        }
+    }
+
+    /// Sends `data` to the client and adds it to the recording
+    fn send(
+        &self,
+        session: &mut server::Session,
+        channel: ChannelId,
+        data: impl Into<String>,
+    ) -> Result<(), russh::Error> {
+        let data = data.into();
+        if let Some(recording) = &self.recording {
+            recording.output(&data);
+        }
+        session.data(channel, data)
+    }
+}
+
 impl server::Handler for Server {
     type Error = russh::Error;
 
@@ -73,32 +238,48 @@ impl server::Handler for Server {
         session: &mut server::Session,
     ) -> Result<(), Self::Error> {
         let cmd = String::from_utf8_lossy(data);
-        info!(ip = ?self.ip, channel = ?channel, cmd = ?cmd, "exec_request");
-
-        let args: Vec<&str> = cmd.split(" ").collect();
-        if let Some(main) = args.first() {
-            info!(ip = ?self.ip, channel = ?channel, cmd = ?cmd, args = ?args, "exec_request");
-            match *main {
-                "ident" | "identity" | "who" => {
-                    session.data(channel, IDENTITY)?;
-                    session.close(channel)?;
-                }
-                "pgp" | "gpg" => {
-                    session.data(channel, PGP_KEY)?;
-                    session.close(channel)?;
-                }
-                _ => {
-                    session.data(
-                        channel,
-                        format!(
-                            "get that dirty \"{}\" away from me, try \"ident, pgp\"\n",
-                            cmd
-                        ),
-                    )?;
-                    session.close(channel)?;
-                }
+        self.record(honeypot::Event::Exec {
+            command: cmd.to_string(),
+        });
+
+        let mut words = cmd.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let args = words.collect::<Vec<_>>();
+        let label = self.commands.label(command, Frontend::Exec, self.role());
+        // not entered, the guard can't be held across awaits
+        let span = command_span(&self.span, "exec", label);
+        span.in_scope(|| info!(ip = %self.client(), channel = ?channel, command = label, args = args.len(), "exec_request"));
+        prometheus::inc_ssh_command("exec", label);
+
+        // `ssh -t host browse`, the channel stays open until the browser is closed
+        let full_screen = self.commands.find(command, Frontend::Exec, self.role());
+        if full_screen.is_some_and(|command| command.full_screen())
+            && let Some(mut state) = self.clients.remove(&channel)
+        {
+            let frame = self.browse(&mut state, true).instrument(span.clone()).await;
+            self.clients.insert(channel, state);
+            if let Some(frame) = frame {
+                self.send(session, channel, frame)?;
+                return Ok(());
             }
         }
+
+        let pty = self
+            .clients
+            .get(&channel)
+            .map(|state| (state.pty, state.col_width, state.colors))
+            .unwrap_or_default();
+        let output = self.run(Frontend::Exec, command, &args, pty.0, pty.1, pty.2);
+        let output = match output.instrument(span.clone()).await {
+            Some(output) => output,
+            None => format!(
+                "get that dirty \"{}\" away from me, try \"{}\"\n",
+                cmd,
+                self.commands.names(Frontend::Exec, self.role()).join(", ")
+            ),
+        };
+        self.send(session, channel, output)?;
+        session.close(channel)?;
         Ok(())
     }
 
@@ -113,20 +294,42 @@ impl server::Handler for Server {
         modes: &[(Pty, u32)],
         session: &mut server::Session,
     ) -> Result<(), Self::Error> {
-        info!(ip = ?self.ip, channel = ?channel, term = ?term, col_width = ?col_width, row_height = ?row_height, pix_width = ?pix_width, pix_height = ?pix_height, modes = ?modes, "pty_request");
+        info!(parent: &self.span, ip = %self.client(), channel = ?channel, term = ?term, col_width = ?col_width, row_height = ?row_height, pix_width = ?pix_width, pix_height = ?pix_height, modes = ?modes, "pty_request");
+        self.record(honeypot::Event::Pty {
+            term: term.into(),
+            cols: col_width,
+            rows: row_height,
+        });
+
+        if let Some(state) = self.clients.get_mut(&channel) {
+            state.pty = true;
+            state.col_width = col_width;
+            state.row_height = row_height;
+            state.colors = ColorDepth::from_term(term);
+        }
 
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        info!(parent: &self.span, ip = %self.client(), channel = ?channel, "shell_request");
+
+        // only the shell's editor turns on bracketed paste, `ssh -t host <command>` leaves it off
         let mut output = Vec::new();
+        if let Some(state) = self.clients.get(&channel)
+            && state.pty
         {
-            let mut clients = self.clients.lock().await;
-            let state = match clients.get_mut(&channel) {
-                Some(s) => s,
-                None => return Ok(()),
-            };
-            redraw_line(state, &mut output);
+            output.push(BRACKETED_PASTE_ON.into());
+            state.editor.redraw(&mut output);
         }
 
         for chunk in output {
-            session.data(channel, chunk)?;
+            self.send(session, channel, chunk)?;
         }
 
         session.channel_success(channel)?;
@@ -139,88 +342,120 @@ impl server::Handler for Server {
         data: &[u8],
         session: &mut server::Session,
     ) -> Result<(), Self::Error> {
+        if let Some(recording) = &self.recording {
+            recording.input(data);
+        }
+
+        let Some(mut state) = self.clients.remove(&channel) else {
+            return Ok(());
+        };
         let mut output = Vec::new();
         let mut should_close = false;
 
-        {
-            let mut clients = self.clients.lock().await;
-            let state = match clients.get_mut(&channel) {
-                Some(s) => s,
-                None => return Ok(()),
-            };
-
-            for &byte in data {
-                if byte == 0x1b || !state.escape.is_empty() {
-                    state.escape.push(byte);
-                    handle_escape(state, &mut output);
-                    continue;
-                }
-                match byte {
-                    b'\r' | b'\n' => {
-                        state.cursor = 0;
-                        let input = state.buffer.trim().to_string();
-                        info!(command = ?input, ip = ?self.ip, "command");
-                        state.buffer.clear();
-
-                        output.push("\r\n".into());
-
-                        match input.as_str() {
-                            "help" => output
-                                .push("Commands: ident, pgp, ping, clear, help, exit\r\n".into()),
-                            "ident" | "identity" | "who" => {
-                                output.push(format!("{}\r\n", IDENTITY.replace("\n", "\r\n")))
-                            }
-                            "gpg" | "pgp" => {
-                                output.push(format!("{}\r\n", PGP_KEY.replace("\n", "\r\n")))
-                            }
-                            "ping" => output.push("pong\r\n".into()),
-                            "clear" => output.push("\x1b[2J\x1b[H".into()),
-                            "exit" => should_close = true,
-                            "" => {}
-                            _ => output.push("Unknown command\r\n".into()),
-                        }
-
-                        if !should_close {
-                            output.push("> ".into());
-                        }
+        for &byte in data {
+            if let Some(browser) = &mut state.browser {
+                match browser.feed(byte, state.row_height) {
+                    Action::None => {}
+                    Action::Redraw => output.push(browser.draw(state.col_width, state.row_height)),
+                    Action::Select => {
+                        let section = self
+                            .section(browser.section(), state.col_width, state.colors)
+                            .await;
+                        browser.show(&section);
+                        output.push(browser.draw(state.col_width, state.row_height));
                     }
-
-                    // CTRL+A
-                    1 => {
-                        state.cursor = 0;
-                        redraw_line(state, &mut output);
+                    Action::Quit => {
+                        output.push(tui::LEAVE.into());
+                        if browser.exec {
+                            should_close = true;
+                            break;
+                        }
+                        state.browser = None;
+                        state.editor.redraw(&mut output);
                     }
+                }
+                continue;
+            }
 
-                    // Ctrl+E
-                    5 => {
-                        state.cursor = state.buffer.len();
-                        redraw_line(state, &mut output);
-                    }
+            let input = match state.editor.feed(byte, &mut output, |words| {
+                self.commands.complete(words, Frontend::Shell, self.role())
+            }) {
+                Input::None => continue,
+                Input::Interrupt | Input::Eof => {
+                    should_close = true;
+                    break;
+                }
+                Input::Line(input) => input,
+            };
 
-                    3 => {
-                        should_close = true;
-                    }
+            if input.is_empty() {
+                state.editor.redraw(&mut output);
+                continue;
+            }
 
-                    127 if !state.buffer.is_empty() && state.cursor > 0 => {
-                        state.cursor -= 1;
-                        state.buffer.remove(state.cursor);
-                        redraw_line(state, &mut output);
-                    }
+            let mut words = input.split_whitespace();
+            let command = words.next().unwrap_or_default();
+            let args = words.collect::<Vec<_>>();
+            let label = self.commands.label(command, Frontend::Shell, self.role());
+            let span = command_span(&self.span, "shell", label);
+            span.in_scope(
+                || info!(command = %redact::text(&input), ip = %self.client(), "command"),
+            );
+            prometheus::inc_ssh_command("shell", label);
+            self.record(honeypot::Event::Command {
+                line: input.clone(),
+            });
+
+            let full_screen = self.commands.find(command, Frontend::Shell, self.role());
+            if full_screen.is_some_and(|command| command.full_screen())
+                && let Some(frame) = self
+                    .browse(&mut state, false)
+                    .instrument(span.clone())
+                    .await
+            {
+                output.push(frame);
+                continue;
+            }
 
-                    byte if byte.is_ascii_graphic() || byte == b' ' => {
-                        state.cursor = state.cursor.min(state.buffer.len());
-                        state.buffer.insert(state.cursor, byte as char);
-                        state.cursor += 1;
-                        redraw_line(state, &mut output);
+            let run = self.run(
+                Frontend::Shell,
+                command,
+                &args,
+                true,
+                state.col_width,
+                state.colors,
+            );
+            match run.instrument(span.clone()).await {
+                Some(page) => {
+                    // the prompt goes on a line of its own, `clear` leaves an empty one
+                    let last = page.rsplit('\n').next().unwrap_or_default();
+                    let newline = !strip_escapes(last).is_empty();
+                    output.push(page);
+                    if newline {
+                        output.push("\r\n".into());
                     }
-
-                    _ => {}
                 }
+                None => output.push("Unknown command\r\n".into()),
             }
+            should_close = self
+                .commands
+                .find(command, Frontend::Shell, self.role())
+                .is_some_and(|command| command.ends_session());
+
+            if should_close {
+                break;
+            }
+            state.editor.redraw(&mut output);
         }
 
+        // leaves the client's terminal as it was
+        if should_close && state.pty {
+            output.push(BRACKETED_PASTE_OFF.into());
+        }
+        self.clients.insert(channel, state);
+
         for chunk in output {
-            session.data(channel, chunk)?;
+            self.send(session, channel, chunk)?;
         }
 
         if should_close {
@@ -230,20 +465,52 @@ impl server::Handler for Server {
         Ok(())
     }
 
+    async fn window_change_request(
+        &mut self,
+        channel: ChannelId,
+        col_width: u32,
+        row_height: u32,
+        pix_width: u32,
+        pix_height: u32,
+        session: &mut server::Session,
+    ) -> Result<(), Self::Error> {
+        debug!(parent: &self.span, ip = %self.client(), channel = ?channel, col_width = ?col_width, row_height = ?row_height, pix_width = ?pix_width, pix_height = ?pix_height, "window_change_request");
+        if let Some(recording) = &self.recording {
+            recording.resize(col_width, row_height);
+        }
+
+        let Some(mut state) = self.clients.remove(&channel) else {
+            return Ok(());
+        };
+        state.col_width = col_width;
+        state.row_height = row_height;
+
+        // pages are wrapped at the terminal's width, the open one is rendered again
+        let mut output = Vec::new();
+        if let Some(browser) = &mut state.browser {
+            let section = self
+                .section(browser.section(), col_width, state.colors)
+                .await;
+            browser.show(&section);
+            output.push(browser.draw(col_width, row_height));
+        }
+        self.clients.insert(channel, state);
+
+        for chunk in output {
+            self.send(session, channel, chunk)?;
+        }
+        Ok(())
+    }
+
     async fn channel_open_session(
         &mut self,
         channel: russh::Channel<server::Msg>,
         reply: server::ChannelOpenHandle,
-        session: &mut server::Session,
+        _session: &mut server::Session,
     ) -> Result<(), Self::Error> {
-        info!(ip = ?self.ip, "connect");
-        {
-            let mut clients = self.clients.lock().await;
-            clients.insert(
-                channel.id(),
-                ClientState::new(channel.id(), session.handle(), self.ip),
-            );
-        }
+        info!(parent: &self.span, ip = %self.client(), "connect");
+        self.clients
+            .insert(channel.id(), ClientState::new(channel.id(), self.ip));
         reply.accept().await;
         Ok(())
     }
@@ -253,19 +520,73 @@ impl server::Handler for Server {
         channel: ChannelId,
         _session: &mut server::Session,
     ) -> Result<(), Self::Error> {
-        info!(ip = ?self.ip, "disconnect");
-        let mut clients = self.clients.lock().await;
-        clients.remove(&channel);
+        info!(parent: &self.span, ip = %self.client(), "disconnect");
+        self.clients.remove(&channel);
         Ok(())
     }
 
+    async fn auth_publickey_offered(
+        &mut self,
+        user: &str,
+        public_key: &PublicKey,
+    ) -> Result<server::Auth, Self::Error> {
+        // visitors' clients offer their keys first, they continue with a password
+        if self.keys.find(public_key).is_some() {
+            return Ok(server::Auth::Accept);
+        }
+        debug!(parent: &self.span, ip = %self.client(), user = %redact::id(user), key = %keys::fingerprint(public_key), "auth_publickey_offered");
+        prometheus::inc_ssh_auth("publickey", "rejected");
+        Ok(server::Auth::reject())
+    }
+
+    async fn auth_publickey(
+        &mut self,
+        user: &str,
+        public_key: &PublicKey,
+    ) -> Result<server::Auth, Self::Error> {
+        // the file may have changed since the key was offered
+        let Some((key, role)) = self.keys.find(public_key) else {
+            prometheus::inc_ssh_auth("publickey", "rejected");
+            return Ok(server::Auth::reject());
+        };
+        let login = Arc::new(Login {
+            user: user.into(),
+            fingerprint: keys::fingerprint(&key),
+            name: key.comment().to_string(),
+            role,
+        });
+
+        self.span.record("user", redact::id(user));
+        self.span.record("auth.method", "publickey");
+        info!(parent: &self.span, ip = %self.client(), user = %redact::id(user), key = %login.fingerprint, name = %login.name, role = role.as_str(), "auth_publickey");
+        self.record(honeypot::Event::Auth {
+            method: "publickey".into(),
+            user: user.into(),
+            password: None,
+        });
+        prometheus::inc_ssh_auth("publickey", "accepted");
+        self.audit
+            .record(&login, self.client().to_string(), AuditAction::Login, None)
+            .await;
+        self.login = Some(login);
+        Ok(server::Auth::Accept)
+    }
+
     async fn auth_keyboard_interactive<'a>(
         &'a mut self,
         user: &str,
         submethods: &str,
         _response: Option<server::Response<'a>>,
     ) -> Result<server::Auth, Self::Error> {
-        info!(ip = ?self.ip, user = ?user, submethods = ?submethods, "auth_keyboard_interactive");
+        self.span.record("user", redact::id(user));
+        self.span.record("auth.method", "keyboard_interactive");
+        info!(parent: &self.span, ip = %self.client(), user = %redact::id(user), submethods = ?submethods, "auth_keyboard_interactive");
+        self.record(honeypot::Event::Auth {
+            method: "keyboard_interactive".into(),
+            user: user.into(),
+            password: None,
+        });
+        prometheus::inc_ssh_auth("keyboard_interactive", "accepted");
         Ok(server::Auth::Accept)
     }
 
@@ -274,71 +595,29 @@ impl server::Handler for Server {
         user: &str,
         password: &str,
     ) -> Result<server::Auth, Self::Error> {
-        info!(ip = ?self.ip, user = ?user, password = ?password, "auth_password");
+        self.span.record("user", redact::id(user));
+        self.span.record("auth.method", "password");
+        info!(parent: &self.span, ip = %self.client(), user = %redact::id(user), password = redact::MASK, "auth_password");
+        // the only place the password is kept
+        self.record(honeypot::Event::Auth {
+            method: "password".into(),
+            user: user.into(),
+            password: Some(password.into()),
+        });
+        prometheus::inc_ssh_auth("password", "accepted");
         Ok(server::Auth::Accept)
     }
 }
 
-fn move_left(state: &mut ClientState, output: &mut Vec<String>) {
-    if state.cursor > 0 {
-        state.cursor -= 1;
-        output.push("\x1b[D".into());
-    }
-}
-
-fn move_right(state: &mut ClientState, output: &mut Vec<String>) {
-    if state.cursor < state.buffer.len() {
-        state.cursor += 1;
-        output.push("\x1b[C".into());
-    }
-}
-
-fn redraw_line(state: &mut ClientState, output: &mut Vec<String>) {
-    output.push("\r".into());
-
-    output.push("\x1b[2K".into());
-
-    output.push("> ".into());
-    output.push(state.buffer.clone());
-
-    let right_shift = state.buffer.len() - state.cursor;
-    if right_shift > 0 {
-        output.push(format!("\x1b[{}D", right_shift));
-    }
-}
-
-type Sequence<'a> = &'a [(&'a [u8], fn(&mut ClientState, &mut Vec<String>))];
-fn handle_escape(state: &mut ClientState, output: &mut Vec<String>) {
-    const SEQUENCES: Sequence = &[
-        // left
-        (b"\x1b[D", move_left),
-        // right
-        (b"\x1b[C", move_right),
-        // delete
-        (b"\x1b[3~", |s, out| {
-            if s.cursor < s.buffer.len() {
-                s.buffer.remove(s.cursor);
-                redraw_line(s, out);
-            }
-        }),
-    ];
-
-    let escape_bytes = &state.escape;
-
-    for (seq, action) in SEQUENCES {
-        if escape_bytes == *seq {
-            action(state, output);
-            state.escape.clear();
-            return;
-        }
-    }
-
-    if !SEQUENCES
-        .iter()
-        .any(|(seq, _)| seq.starts_with(escape_bytes))
-    {
-        state.escape.clear();
-    }
+/// A child of the session's span for a single command
+fn command_span(session: &Span, mode: &'static str, command: &'static str) -> Span {
+    info_span!(
+        parent: session,
+        "ssh_command",
+        otel.name = format!("{mode} {command}"),
+        mode,
+        command,
+    )
 }
 
 fn load_or_generate_key(path: &str) -> anyhow::Result<PrivateKey> {
@@ -356,11 +635,14 @@ fn load_or_generate_key(path: &str) -> anyhow::Result<PrivateKey> {
     }
 }
 
-const KEY_PATH: &str = "./config/ssh_host_ed25519";
-
-pub async fn init(_config: Arc<config::types::Config>) -> anyhow::Result<()> {
-    let key = load_or_generate_key(KEY_PATH)?;
-    let config = Arc::new(russh::server::Config {
+/// Serves SSH until `shutdown` is cancelled, connected clients are then disconnected
+pub async fn init(
+    config: Arc<config::types::Config>,
+    commands: Arc<Registry>,
+    shutdown: CancellationToken,
+) -> anyhow::Result<()> {
+    let key = load_or_generate_key(&config.ssh.host_key)?;
+    let russh_config = Arc::new(russh::server::Config {
         inactivity_timeout: Some(std::time::Duration::from_secs(600)),
         auth_rejection_time: std::time::Duration::from_secs(3),
         auth_rejection_time_initial: Some(std::time::Duration::from_secs(0)),
@@ -368,14 +650,99 @@ pub async fn init(_config: Arc<config::types::Config>) -> anyhow::Result<()> {
         ..Default::default()
     });
 
-    let mut sh = Server {
-        clients: Arc::new(Mutex::new(HashMap::new())),
+    let keys = Arc::new(Keys::load(&config.ssh.authorized_keys));
+    keys::spawn_watcher(Arc::clone(&keys))?;
+
+    let sh = Server {
+        clients: HashMap::new(),
         ip: None,
+        session: None,
+        span: Span::none(),
+        recording: None,
+        commands,
+        keys,
+        audit: Arc::new(Audit::new(&config.ssh.audit_log)),
+        login: None,
     };
 
-    let socket = TcpListener::bind(("0.0.0.0", 2222)).await?;
-    let server = sh.run_on_socket(config, &socket);
+    let connections = Connections::default();
+    let mut servers = Vec::new();
+    for address in &config.ssh.listen {
+        let address: ListenAddress = address.parse().map_err(|e| anyhow!("ssh.listen: {e}"))?;
+        let listener = Listener::bind(address.clone(), None)
+            .map_err(|e| anyhow!("failed to bind {address}: {e}"))?;
+
+        servers.push(serve(
+            listener,
+            Arc::clone(&russh_config),
+            sh.clone(),
+            Arc::clone(&connections),
+        ));
+    }
 
     info!("SSH server up");
-    Ok(server.await?)
+    LISTENING.store(true, Ordering::Relaxed);
+    tokio::select! {
+        _ = join_all(servers) => {}
+        // dropping the listeners stops accepting new connections
+        () = shutdown.cancelled() => {}
+    }
+    LISTENING.store(false, Ordering::Relaxed);
+
+    let handles = connections
+        .lock()
+        .await
+        .values()
+        .cloned()
+        .collect::<Vec<_>>();
+    info!("disconnecting {} SSH clients", handles.len());
+
+    for handle in handles {
+        let _ = handle
+            .disconnect(
+                russh::Disconnect::ByApplication,
+                "server shutting down".into(),
+                "en".into(),
+            )
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Same as `Server::run_on_socket`, but for TCP and Unix listeners
+async fn serve(
+    mut listener: Listener,
+    config: Arc<server::Config>,
+    mut sh: Server,
+    connections: Connections,
+) {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    loop {
+        let (stream, peer) = listener.accept().await;
+        let handler = sh.new_client(Some(peer));
+        let config = Arc::clone(&config);
+        let connections = Arc::clone(&connections);
+
+        tokio::spawn(async move {
+            let result = match server::run_stream(config, stream, handler).await {
+                Ok(session) => {
+                    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+                    connections.lock().await.insert(id, session.handle());
+                    let result = session.await;
+                    connections.lock().await.remove(&id);
+                    result
+                }
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = result {
+                debug!(
+                    "{}: SSH session ended with an error: {e}",
+                    redact::ip(peer.ip())
+                );
+            }
+        });
+    }
 }
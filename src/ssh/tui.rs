@@ -0,0 +1,202 @@
+use unicode_width::UnicodeWidthStr;
+
+use crate::webserver::render::Style;
+
+/// Alternate screen, no cursor and no line wrapping, long lines are cut off by the terminal
+pub const ENTER: &str = "\x1b[?1049h\x1b[?25l\x1b[?7l";
+/// Back to the shell's screen as it was before
+pub const LEAVE: &str = "\x1b[?7h\x1b[?25h\x1b[?1049l";
+
+/// The tab bar, the status line and at least one line of the section
+pub const MIN_ROWS: u32 = 3;
+
+const HELP: &str = " ←/→ section  ↑/↓ PgUp/PgDn scroll  q quit";
+
+/// What a byte of input changed
+pub enum Action {
+    None,
+    // the scroll position changed
+    Redraw,
+    // another section was selected, it has to be rendered again
+    Select,
+    Quit,
+}
+
+/// Full-screen browser of the site's sections
+#[derive(Clone, Debug)]
+pub struct Browser {
+    sections: Vec<String>,
+    selected: usize,
+    // the selected section, each line starts with the style still active from the lines before
+    lines: Vec<String>,
+    scroll: usize,
+    escape: Vec<u8>,
+    // opened by `ssh -t host browse`, the channel closes with the browser
+    pub exec: bool,
+}
+
+impl Browser {
+    pub fn new(sections: Vec<String>, exec: bool) -> Self {
+        Self {
+            sections,
+            selected: 0,
+            lines: Vec::new(),
+            scroll: 0,
+            escape: Vec::new(),
+            exec,
+        }
+    }
+
+    pub fn section(&self) -> &str {
+        self.sections
+            .get(self.selected)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// Replaces the selected section's content, rendered for the terminal's width
+    pub fn show(&mut self, page: &str) {
+        let mut style = String::new();
+        self.lines = page
+            .trim_end_matches('\n')
+            .split('\n')
+            .map(|line| {
+                let styled = format!("{style}{line}");
+                for sequence in sgr(line) {
+                    match sequence {
+                        "\x1b[0m" | "\x1b[m" => style.clear(),
+                        sequence => style.push_str(sequence),
+                    }
+                }
+                styled
+            })
+            .collect();
+    }
+
+    /// Handles one byte of input, `rows` is the terminal's height
+    pub fn feed(&mut self, byte: u8, rows: u32) -> Action {
+        let page = height(rows);
+
+        if byte == 0x1b || !self.escape.is_empty() {
+            self.escape.push(byte);
+            let sequence = match self.escape.as_slice() {
+                [0x1b] | [0x1b, b'[' | b'O'] => return Action::None,
+                [0x1b, b'[', .., last]
+                    if !(0x40..=0x7e).contains(last) && self.escape.len() < 16 =>
+                {
+                    return Action::None;
+                }
+                _ => std::mem::take(&mut self.escape),
+            };
+            return match &sequence[1..] {
+                b"[A" | b"OA" => self.scroll_to(self.scroll.saturating_sub(1), rows),
+                b"[B" | b"OB" => self.scroll_to(self.scroll + 1, rows),
+                b"[5~" => self.scroll_to(self.scroll.saturating_sub(page), rows),
+                b"[6~" => self.scroll_to(self.scroll + page, rows),
+                b"[H" | b"OH" | b"[1~" | b"[7~" => self.scroll_to(0, rows),
+                b"[F" | b"OF" | b"[4~" | b"[8~" => self.scroll_to(usize::MAX, rows),
+                // Shift-Tab
+                b"[D" | b"OD" | b"[Z" => self.select(self.selected + self.sections.len() - 1),
+                b"[C" | b"OC" => self.select(self.selected + 1),
+                _ => Action::None,
+            };
+        }
+
+        match byte {
+            // Ctrl-C, Ctrl-D
+            b'q' | 3 | 4 => Action::Quit,
+            b'k' => self.scroll_to(self.scroll.saturating_sub(1), rows),
+            b'j' | b'\r' => self.scroll_to(self.scroll + 1, rows),
+            b'b' => self.scroll_to(self.scroll.saturating_sub(page), rows),
+            b' ' => self.scroll_to(self.scroll + page, rows),
+            b'g' => self.scroll_to(0, rows),
+            b'G' => self.scroll_to(usize::MAX, rows),
+            b'h' => self.select(self.selected + self.sections.len() - 1),
+            b'l' | b'\t' => self.select(self.selected + 1),
+            b'1'..=b'9' if usize::from(byte - b'1') < self.sections.len() => {
+                self.select(usize::from(byte - b'1'))
+            }
+            // Ctrl-L
+            12 => Action::Redraw,
+            _ => Action::None,
+        }
+    }
+
+    fn scroll_to(&mut self, scroll: usize, rows: u32) -> Action {
+        let scroll = scroll.min(self.lines.len().saturating_sub(height(rows)));
+        if scroll == self.scroll {
+            return Action::None;
+        }
+        self.scroll = scroll;
+        Action::Redraw
+    }
+
+    /// Selects section `index` (modulo the number of sections) from its top
+    fn select(&mut self, index: usize) -> Action {
+        if self.sections.is_empty() {
+            return Action::None;
+        }
+        let index = index % self.sections.len();
+        if index == self.selected {
+            return Action::None;
+        }
+        self.selected = index;
+        self.scroll = 0;
+        Action::Select
+    }
+
+    /// The whole screen, the sections as tabs on top and the status line at the bottom
+    pub fn draw(&mut self, cols: u32, rows: u32) -> String {
+        let cols = cols as usize;
+        let height = height(rows);
+        // a resize may have made the section shorter than the screen
+        self.scroll = self.scroll.min(self.lines.len().saturating_sub(height));
+
+        let reverse = Style::new().reverse(true).ansi_code();
+        let mut frame = String::from("\x1b[H\x1b[0m");
+        for (index, section) in self.sections.iter().enumerate() {
+            let tab = format!(" {} {section} ", index + 1);
+            match index == self.selected {
+                true => frame.push_str(&format!("{reverse}{tab}\x1b[0m")),
+                false => frame.push_str(&tab),
+            }
+        }
+        frame.push_str("\x1b[K");
+
+        for row in 0..height {
+            let line = self
+                .lines
+                .get(self.scroll + row)
+                .map(String::as_str)
+                .unwrap_or_default();
+            frame.push_str(&format!("\r\n\x1b[0m{line}\x1b[0m\x1b[K"));
+        }
+
+        let position = format!(
+            "{}-{}/{} ",
+            (self.scroll + 1).min(self.lines.len()),
+            (self.scroll + height).min(self.lines.len()),
+            self.lines.len()
+        );
+        let gap = cols.saturating_sub(HELP.width() + position.width());
+        frame.push_str(&format!(
+            "\r\n{reverse}{HELP}{}{position}\x1b[0m",
+            " ".repeat(gap)
+        ));
+        frame
+    }
+}
+
+/// Lines left for the section
+fn height(rows: u32) -> usize {
+    rows.saturating_sub(MIN_ROWS - 1).max(1) as usize
+}
+
+/// The SGR (style) sequences in `line`, in order
+fn sgr(line: &str) -> impl Iterator<Item = &str> {
+    line.match_indices("\x1b[").filter_map(|(start, _)| {
+        let rest = &line[start + 2..];
+        let end = rest.find(|c: char| !(c.is_ascii_digit() || c == ';'))?;
+        (rest[end..].starts_with('m')).then(|| &line[start..start + 2 + end + 1])
+    })
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use serde::Serialize;
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::alert::{self, Severity};
+
+/// How long subsystems get to finish their work after SIGTERM/SIGINT
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long the subsystems stopped last get once all others have stopped
+const LAST_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Restart backoff, doubled after every failure up to the max
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(300);
+/// A subsystem running this long without failing starts over at the base backoff
+const BACKOFF_RESET: Duration = Duration::from_secs(600);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    Running,
+    Restarting,
+    Failed,
+    Stopped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Status {
+    pub state: State,
+    // a failing critical subsystem shuts the whole process down
+    pub critical: bool,
+    pub restarts: u32,
+    pub since: DateTime<Utc>,
+    pub last_error: Option<String>,
+}
+
+/// The state of every subsystem, shown on /health
+#[derive(Clone, Default)]
+pub struct Health(Arc<Mutex<BTreeMap<&'static str, Status>>>);
+
+impl Health {
+    pub fn snapshot(&self) -> BTreeMap<&'static str, Status> {
+        self.0.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    fn update(&self, name: &'static str, f: impl FnOnce(&mut Status)) {
+        if let Ok(mut subsystems) = self.0.lock()
+            && let Some(status) = subsystems.get_mut(name)
+        {
+            f(status);
+            status.since = Utc::now();
+        }
+    }
+
+    fn set(&self, name: &'static str, state: State) {
+        self.update(name, |s| s.state = state);
+    }
+}
+
+/// Runs the subsystems, restarts the ones which fail and shuts everything down on SIGTERM/SIGINT
+pub struct Supervisor {
+    shutdown: CancellationToken,
+    health: Health,
+    tasks: JoinSet<()>,
+    // the subsystems from spawn_last, stopped once the others are
+    last: (CancellationToken, JoinSet<()>),
+    failed: Arc<Mutex<Option<&'static str>>>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self {
+            shutdown: CancellationToken::new(),
+            health: Health::default(),
+            tasks: JoinSet::new(),
+            last: (CancellationToken::new(), JoinSet::new()),
+            failed: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn health(&self) -> Health {
+        self.health.clone()
+    }
+
+    /// Runs `run` until the shutdown token is cancelled, `run` has to return once it is.
+    /// Returning before that counts as a failure.
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, critical: bool, run: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        self.start(name, critical, false, run);
+    }
+
+    /// Same as `spawn`, but the token is only cancelled once all other subsystems have stopped
+    /// (or didn't in time), for the alerts they raise while draining
+    pub fn spawn_last<F, Fut>(&mut self, name: &'static str, run: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        self.start(name, false, true, run);
+    }
+
+    fn start<F, Fut>(&mut self, name: &'static str, critical: bool, last: bool, run: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        if let Ok(mut subsystems) = self.health.0.lock() {
+            subsystems.insert(
+                name,
+                Status {
+                    state: State::Running,
+                    critical,
+                    restarts: 0,
+                    since: Utc::now(),
+                    last_error: None,
+                },
+            );
+        }
+
+        // a critical failure still shuts everything down
+        let shutdown = self.shutdown.clone();
+        let (stop, tasks) = match last {
+            true => (self.last.0.clone(), &mut self.last.1),
+            false => (self.shutdown.clone(), &mut self.tasks),
+        };
+        let health = self.health.clone();
+        let failed = Arc::clone(&self.failed);
+
+        tasks.spawn(async move {
+            let mut attempt = 0;
+
+            loop {
+                health.set(name, State::Running);
+                let started = Instant::now();
+                // a panic counts as a failure instead of silently ending the subsystem
+                let result = AssertUnwindSafe(run(stop.clone()))
+                    .catch_unwind()
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("panicked")));
+
+                if stop.is_cancelled() {
+                    if let Err(e) = result {
+                        warn!("{name} stopped with an error: {e:#}");
+                    }
+                    health.set(name, State::Stopped);
+                    return;
+                }
+
+                let error = match result {
+                    Ok(()) => "exited unexpectedly".to_string(),
+                    Err(e) => format!("{e:#}"),
+                };
+                health.update(name, |s| s.last_error = Some(error.clone()));
+
+                if critical {
+                    health.set(name, State::Failed);
+                    alert::notify(
+                        Severity::Critical,
+                        name,
+                        format!("failed, shutting down: {error}"),
+                    );
+                    if let Ok(mut failed) = failed.lock() {
+                        failed.get_or_insert(name);
+                    }
+                    shutdown.cancel();
+                    return;
+                }
+
+                if started.elapsed() > BACKOFF_RESET {
+                    attempt = 0;
+                }
+                let backoff = BACKOFF_BASE
+                    .saturating_mul(2u32.saturating_pow(attempt))
+                    .min(BACKOFF_MAX);
+                attempt += 1;
+
+                health.update(name, |s| {
+                    s.state = State::Restarting;
+                    s.restarts += 1;
+                });
+                alert::notify(
+                    Severity::Error,
+                    name,
+                    format!("failed, restarting in {}s: {error}", backoff.as_secs()),
+                );
+
+                tokio::select! {
+                    () = tokio::time::sleep(backoff) => {}
+                    () = stop.cancelled() => {
+                        health.set(name, State::Stopped);
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Waits for SIGTERM/SIGINT (or a critical subsystem failing) and drains all subsystems.
+    /// A second signal exits right away.
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut interrupt = signal(SignalKind::interrupt())?;
+
+        tokio::select! {
+            _ = terminate.recv() => info!("SIGTERM received, shutting down"),
+            _ = interrupt.recv() => info!("SIGINT received, shutting down"),
+            () = self.shutdown.cancelled() => {}
+        }
+        self.shutdown.cancel();
+
+        let drain = async { while self.tasks.join_next().await.is_some() {} };
+        tokio::select! {
+            () = drain => info!("all subsystems stopped"),
+            () = tokio::time::sleep(DRAIN_TIMEOUT) => alert::notify(
+                Severity::Error,
+                "Shutdown",
+                format!("subsystems didn't stop within {}s, exiting", DRAIN_TIMEOUT.as_secs()),
+            ),
+            _ = terminate.recv() => warn!("second SIGTERM received, exiting"),
+            _ = interrupt.recv() => warn!("second SIGINT received, exiting"),
+        }
+
+        // delivers what was raised while the others drained
+        let (last, mut tasks) = self.last;
+        last.cancel();
+        let drain = async { while tasks.join_next().await.is_some() {} };
+        if tokio::time::timeout(LAST_DRAIN_TIMEOUT, drain)
+            .await
+            .is_err()
+        {
+            error!(
+                "the last subsystems didn't stop within {}s, exiting",
+                LAST_DRAIN_TIMEOUT.as_secs()
+            );
+        }
+
+        match self.failed.lock().ok().and_then(|f| *f) {
+            Some(name) => Err(anyhow!("{name} failed")),
+            None => Ok(()),
+        }
+    }
+}
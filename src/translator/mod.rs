@@ -69,14 +69,14 @@ impl Translator {
     }
 
     pub async fn languages(&self) -> anyhow::Result<Vec<LanguagesResponse>> {
-        let resp = self
-            .client
-            .get(self.url.join("/languages")?)
-            .send()
-            .await
-            .map_err(|e| ApiError {
-                error: format!("request failed: {e}"),
-            })?;
+        let resp = crate::prometheus::send_upstream(
+            "translator",
+            self.client.get(self.url.join("/languages")?),
+        )
+        .await
+        .map_err(|e| ApiError {
+            error: format!("request failed: {e}"),
+        })?;
 
         let text = resp.text().await.map_err(|e| ApiError {
             error: format!("failed to read response: {e}"),
@@ -99,15 +99,14 @@ impl Translator {
             payload["api_key"] = token.clone().into();
         }
 
-        let resp = self
-            .client
-            .post(self.url.join("/detect")?)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| ApiError {
-                error: format!("request failed: {e}"),
-            })?;
+        let resp = crate::prometheus::send_upstream(
+            "translator",
+            self.client.post(self.url.join("/detect")?).json(&payload),
+        )
+        .await
+        .map_err(|e| ApiError {
+            error: format!("request failed: {e}"),
+        })?;
 
         let text = resp.text().await.map_err(|e| ApiError {
             error: format!("failed to read response: {e}"),
@@ -150,15 +149,16 @@ impl Translator {
             payload["api_key"] = token.clone().into();
         }
 
-        let resp = self
-            .client
-            .post(self.url.join("/translate")?)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| ApiError {
-                error: e.to_string(),
-            })?;
+        let resp = crate::prometheus::send_upstream(
+            "translator",
+            self.client
+                .post(self.url.join("/translate")?)
+                .json(&payload),
+        )
+        .await
+        .map_err(|e| ApiError {
+            error: e.to_string(),
+        })?;
 
         if !resp.status().is_success() {
             let status = resp.status();
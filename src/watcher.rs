@@ -0,0 +1,73 @@
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::mpsc,
+};
+use tracing::{error, info};
+
+/// Editors write files in multiple steps, wait for them to settle before reloading
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Calls `reload` when something in `path` (a file or a directory) changes
+/// or the process receives SIGHUP
+pub fn spawn<F, Fut>(name: &'static str, path: PathBuf, reload: F) -> anyhow::Result<()>
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    // events carry absolute paths
+    let path = std::path::absolute(path)?;
+    let target = path.clone();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event)
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) && event.paths.iter().any(|p| p.starts_with(&target)) =>
+            {
+                let _ = tx.send(());
+            }
+            Ok(_) => {}
+            Err(e) => error!("{name}: file watcher error: {e}"),
+        })?;
+
+    // Watch the parent of files, editors often replace them instead of writing in place
+    let watched = if path.is_dir() {
+        path.as_path()
+    } else {
+        path.parent().unwrap_or(Path::new("."))
+    };
+    watcher.watch(watched, RecursiveMode::NonRecursive)?;
+
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    tokio::spawn(async move {
+        // dropping the watcher stops it
+        let _watcher = watcher;
+
+        loop {
+            tokio::select! {
+                Some(()) = rx.recv() => {
+                    tokio::time::sleep(DEBOUNCE).await;
+                    while rx.try_recv().is_ok() {}
+                    info!("{name}: {} changed, reloading", path.display());
+                }
+                Some(()) = hangup.recv() => info!("{name}: SIGHUP received, reloading"),
+                else => break,
+            }
+
+            reload().await;
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::webserver::render::{
+    Style,
+    builders::{COLOR_MAPPING, CanvasBuilder, TextBlobBuilder},
+    color::bit4::Bit4Color,
+    object::Objects,
+};
+
+/// The canvas for `q`, or how to use it when there is no `q`
+pub fn canvas(q: Option<String>) -> Vec<Objects> {
+    match q {
+        Some(q) => vec![
+            CanvasBuilder::new(q).into(),
+            TextBlobBuilder::new("\n").style(Style::new()).into(),
+        ],
+        None => {
+            let mut list = COLOR_MAPPING
+                .iter()
+                .map(|(key, value)| format!("{key}: {value:?}"))
+                .collect::<Vec<_>>();
+            list.push("NL: NewLine".into());
+            vec![
+				TextBlobBuilder::new("Canvas\n\n")
+					.style(Style::new().fg(Bit4Color::RED))
+					.into(),
+				TextBlobBuilder::new("Use the q query parameter to use this canvas api\n\n").into(),
+				TextBlobBuilder::new(list.join("\n"))
+					.style(Style::new().fg(Bit4Color::YELLOW))
+					.into(),
+				TextBlobBuilder::new(
+					"\n\nExample: https://kybe.xyz/canvas?q=BLBLBLBLBLBLBLBLBLBLNLRRRRRRRRRRNLYYYYYYYYYY\n",
+				)
+				.into(),
+			]
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ContentError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("invalid page {path}: {source}")]
+    Parse {
+        path: String,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("{page}: included page {name} does not exist")]
+    MissingInclude { page: String, name: String },
+
+    #[error("{page}: include cycle through {name}")]
+    IncludeCycle { page: String, name: String },
+
+    #[error("{page}: route {route} must start with /")]
+    InvalidRoute { page: String, route: String },
+
+    #[error("route {route} is defined by {first} and {second}")]
+    DuplicateRoute {
+        route: String,
+        first: String,
+        second: String,
+    },
+}
@@ -0,0 +1,420 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use arc_swap::ArcSwap;
+use tracing::{error, info, warn};
+
+use crate::{
+    external::lastfm::{self, Response},
+    webserver::{
+        RequestContext, TERMINAL_PROMPT, common,
+        content::{
+            error::ContentError,
+            types::{DynamicBlock, Element, PageFile, Role},
+        },
+        render::{
+            Style, Theme,
+            builders::{CodeBlockBuilder, ImageBuilder, TextBlobBuilder},
+            object::Object,
+        },
+    },
+};
+
+pub mod error;
+pub mod types;
+
+/// Directory inside the assets directory holding the page files
+const PAGES_DIR: &str = "pages";
+
+/// The current pages, swapped out when the pages directory changes
+pub type SharedPages = Arc<ArcSwap<ContentPages>>;
+
+/// A page loaded from the pages directory with all includes and files resolved
+pub struct ContentPage {
+    pub route: String,
+    pub title: String,
+    content: Vec<Element>,
+}
+
+/// Data for the dynamic blocks of a page, fetched per request
+#[derive(Default)]
+pub struct DynamicData {
+    pub playing: Option<Response>,
+}
+
+#[derive(Default)]
+pub struct ContentPages {
+    pages: Vec<Arc<ContentPage>>,
+}
+
+impl ContentPages {
+    /// Loads every `*.toml` file in `<assets_dir>/pages`, the ones with a route become pages
+    pub fn load(assets_dir: &Path) -> Result<Self, ContentError> {
+        let pages_dir = assets_dir.join(PAGES_DIR);
+        let read_error = |path: &Path| {
+            let path = path.display().to_string();
+            move |source| ContentError::Read { path, source }
+        };
+
+        let mut files = HashMap::new();
+        for entry in fs::read_dir(&pages_dir).map_err(read_error(&pages_dir))? {
+            let path = entry.map_err(read_error(&pages_dir))?.path();
+            if path.extension().is_none_or(|e| e != "toml") {
+                continue;
+            }
+
+            let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+
+            let contents = fs::read_to_string(&path).map_err(read_error(&path))?;
+            let file: PageFile =
+                toml::from_str(&contents).map_err(|source| ContentError::Parse {
+                    path: path.display().to_string(),
+                    source,
+                })?;
+
+            files.insert(name, file);
+        }
+
+        let mut pages: Vec<Arc<ContentPage>> = Vec::new();
+        let mut names: HashMap<&str, &str> = HashMap::new();
+
+        let mut sorted = files.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|(name, _)| *name);
+
+        for (name, file) in sorted {
+            let Some(route) = &file.route else {
+                continue;
+            };
+
+            if !route.starts_with('/') {
+                return Err(ContentError::InvalidRoute {
+                    page: name.clone(),
+                    route: route.clone(),
+                });
+            }
+
+            if let Some(first) = names.insert(route, name) {
+                return Err(ContentError::DuplicateRoute {
+                    route: route.clone(),
+                    first: first.into(),
+                    second: name.clone(),
+                });
+            }
+
+            let mut stack = vec![name.as_str()];
+            pages.push(Arc::new(ContentPage {
+                route: route.clone(),
+                title: file.title.clone().unwrap_or(route.clone()),
+                content: resolve(&file.content, &files, assets_dir, &mut stack)?,
+            }));
+        }
+
+        info!("loaded {} pages from {}", pages.len(), pages_dir.display());
+
+        Ok(Self { pages })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<ContentPage>> {
+        self.pages.iter()
+    }
+
+    pub fn get(&self, route: &str) -> Option<&Arc<ContentPage>> {
+        self.pages.iter().find(|p| p.route == route)
+    }
+}
+
+/// Watches the pages directory (and SIGHUP) and swaps in the new pages once all of them load
+pub fn spawn_watcher(pages: SharedPages, assets_dir: PathBuf) -> anyhow::Result<()> {
+    crate::watcher::spawn("content", assets_dir.join(PAGES_DIR), move || {
+        let pages = Arc::clone(&pages);
+        let assets_dir = assets_dir.clone();
+        async move { reload(&pages, &assets_dir) }
+    })
+}
+
+fn reload(pages: &SharedPages, assets_dir: &Path) {
+    let new = match ContentPages::load(assets_dir) {
+        Ok(new) => new,
+        Err(e) => {
+            error!("content reload failed, keeping the current pages: {e}");
+            return;
+        }
+    };
+
+    let old = pages.load();
+    for page in new.iter().filter(|p| old.get(&p.route).is_none()) {
+        warn!(
+            "content: new page {}, restart required to serve it",
+            page.route
+        );
+    }
+    for page in old.iter().filter(|p| new.get(&p.route).is_none()) {
+        info!("content: page {} removed", page.route);
+    }
+
+    pages.store(Arc::new(new));
+}
+
+/// Replaces includes with the content of the included file and files with their text
+fn resolve<'a>(
+    elements: &[Element],
+    files: &'a HashMap<String, PageFile>,
+    assets_dir: &Path,
+    stack: &mut Vec<&'a str>,
+) -> Result<Vec<Element>, ContentError> {
+    let page = stack.first().copied().unwrap_or_default().to_string();
+    let mut output = Vec::with_capacity(elements.len());
+
+    for element in elements {
+        let element = match element {
+            Element::Include { name } => {
+                let Some((name, file)) = files.get_key_value(name) else {
+                    return Err(ContentError::MissingInclude {
+                        page,
+                        name: name.clone(),
+                    });
+                };
+
+                if stack.contains(&name.as_str()) {
+                    return Err(ContentError::IncludeCycle {
+                        page,
+                        name: name.clone(),
+                    });
+                }
+
+                stack.push(name);
+                output.extend(resolve(&file.content, files, assets_dir, stack)?);
+                stack.pop();
+                continue;
+            }
+            Element::File { path, role } => {
+                let path = assets_dir.join(path);
+                let text = fs::read_to_string(&path).map_err(|source| ContentError::Read {
+                    path: path.display().to_string(),
+                    source,
+                })?;
+
+                Element::Text {
+                    text: format!("{}\n", text.trim()),
+                    role: *role,
+                }
+            }
+            Element::Label { label, content } => Element::Label {
+                label: label.clone(),
+                content: resolve(content, files, assets_dir, stack)?,
+            },
+            Element::CodeBlock {
+                title,
+                language,
+                content,
+            } => Element::CodeBlock {
+                title: title.clone(),
+                language: language.clone(),
+                content: resolve(content, files, assets_dir, stack)?,
+            },
+            element => element.clone(),
+        };
+
+        output.push(element);
+    }
+
+    Ok(output)
+}
+
+impl ContentPage {
+    pub fn uses(&self, block: DynamicBlock) -> bool {
+        fn uses(elements: &[Element], block: DynamicBlock) -> bool {
+            elements.iter().any(|e| match e {
+                Element::Dynamic { name } => *name == block,
+                Element::Label { content, .. } | Element::CodeBlock { content, .. } => {
+                    uses(content, block)
+                }
+                _ => false,
+            })
+        }
+
+        uses(&self.content, block)
+    }
+
+    pub fn objects(&self, ctx: &RequestContext, data: &DynamicData) -> Vec<Object> {
+        build(&self.content, &Theme::default(), ctx, data)
+    }
+}
+
+/// The "Currently Listening" block of the root page on its own
+pub fn now_playing(ctx: &RequestContext, data: &DynamicData) -> Vec<Object> {
+    dynamic(DynamicBlock::NowPlaying, &Theme::default(), ctx, data)
+}
+
+/// `KYBE_BACKEND_ASSETS_DIR` or `assets`
+pub fn assets_dir() -> PathBuf {
+    std::env::var("KYBE_BACKEND_ASSETS_DIR")
+        .unwrap_or("assets".into())
+        .into()
+}
+
+fn style(theme: &Theme, role: Role) -> Option<Style> {
+    match role {
+        Role::Title => Some(theme.title.clone()),
+        Role::Subtitle => Some(theme.subtitle.clone()),
+        Role::Section => Some(theme.section.clone()),
+        Role::SubSection => Some(theme.sub_section.clone()),
+        Role::Label => Some(theme.label.clone()),
+        Role::Text => Some(theme.text.clone()),
+        Role::Link => Some(theme.link.clone()),
+        Role::LinkSeparator => Some(theme.link_separator.clone()),
+        Role::Comment => Some(theme.comment.clone()),
+        Role::TerminalPrompt => Some(theme.terminal_prompt.clone()),
+        Role::Raw => None,
+    }
+}
+
+fn build(
+    elements: &[Element],
+    theme: &Theme,
+    ctx: &RequestContext,
+    data: &DynamicData,
+) -> Vec<Object> {
+    let url = |url: &str| {
+        if url.starts_with('/') {
+            ctx.url(url)
+        } else {
+            url.to_string()
+        }
+    };
+
+    let mut output = Vec::new();
+
+    for element in elements {
+        match element {
+            Element::Title { text, underline } => {
+                if *underline {
+                    output.extend(theme.title_underlined(text));
+                } else {
+                    output.push(theme.title(text.as_str()).into());
+                }
+            }
+            Element::Subtitle { text } => output.push(theme.subtitle(text.as_str()).into()),
+            Element::Section { text } => output.extend(theme.section_underlined(text)),
+            Element::SubSection { text } => output.extend(theme.sub_section_underlined(text)),
+            Element::Text { text, role } => {
+                let blob = match style(theme, *role) {
+                    Some(style) => TextBlobBuilder::new(text).style(style),
+                    None => theme.raw(text.as_str()),
+                };
+                output.push(blob.into());
+            }
+            Element::Link {
+                text,
+                url: link,
+                role,
+            } => {
+                let link = url(link);
+                let text = text.clone().unwrap_or(link.clone());
+
+                let blob = match (role, style(theme, *role)) {
+                    (Role::Link, _) => theme.link_colored(text, &link),
+                    (_, Some(style)) => theme.link_colored(text, &link).style(style),
+                    (_, None) => theme.link(text, &link),
+                };
+                output.push(blob.into());
+            }
+            Element::Label { label, content } => {
+                output.extend(theme.label(label, build(content, theme, ctx, data)));
+            }
+            Element::CodeBlock {
+                title,
+                language,
+                content,
+            } => {
+                let mut block = CodeBlockBuilder::new(build(content, theme, ctx, data));
+                if let Some(title) = title {
+                    block = block.title(title);
+                }
+                if let Some(language) = language {
+                    block = block.language(language);
+                }
+                output.push(block.into());
+            }
+            Element::Image {
+                url: image,
+                alt,
+                width,
+                height,
+            } => output.push(ImageBuilder::new(url(image), alt, *width, *height).into()),
+            // resolved while loading
+            Element::File { .. } | Element::Include { .. } => {}
+            Element::Dynamic { name } => output.extend(dynamic(*name, theme, ctx, data)),
+            Element::Footer => output.extend(common::footer::footer().into_iter().flatten()),
+        }
+    }
+
+    output
+}
+
+fn dynamic(
+    block: DynamicBlock,
+    theme: &Theme,
+    ctx: &RequestContext,
+    data: &DynamicData,
+) -> Vec<Object> {
+    match block {
+        DynamicBlock::CurlHint if ctx.render.format.is_terminal() => vec![],
+        DynamicBlock::CurlHint => vec![
+            CodeBlockBuilder::new(vec![
+                theme.terminal_prompt(TERMINAL_PROMPT).into(),
+                TextBlobBuilder::new("curl https://kybe.xyz").into(),
+            ])
+            .into(),
+            theme.raw("\n").into(),
+        ],
+        DynamicBlock::NowPlaying => {
+            let mut output: Vec<Object> = theme
+                .title_underlined("Currently Listening")
+                .into_iter()
+                .collect();
+            output.extend(theme.label(
+                "Playing",
+                vec![
+                    theme
+                        .link_colored(
+                            if data.playing.is_some() {
+                                "True"
+                            } else {
+                                "False"
+                            },
+                            "https://metrics.kybe.xyz/public-dashboards/f64d242587e14e2689b22e0ff542a1e9",
+                        )
+                        .into(),
+                    theme.comment(" (click me)\n").into(),
+                ],
+            ));
+
+            if let Some(playing) = &data.playing {
+                output.extend(theme.label(
+                    "Song",
+                    vec![
+                        theme
+                            .link_colored(playing.name.as_str(), &playing.url)
+                            .into(),
+                        TextBlobBuilder::new(" — ").style(theme.link.clone()).into(), // oh no, a em-dash kek (- looks to short lol)
+                        theme
+                            .link_colored(
+                                format!("{}\n", playing.artist.as_str()).as_str(),
+                                &lastfm::artist_url(&playing.artist),
+                            )
+                            .into(),
+                    ],
+                ));
+            }
+
+            output
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Deserialize;
+
+/// A file in the pages directory, files without a route can only be included
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PageFile {
+    pub route: Option<String>,
+    // Shown as the HTML title, defaults to the route
+    pub title: Option<String>,
+    #[serde(default)]
+    pub content: Vec<Element>,
+}
+
+/// The Theme style an element is drawn with
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Title,
+    Subtitle,
+    Section,
+    SubSection,
+    Label,
+    #[default]
+    Text,
+    Link,
+    LinkSeparator,
+    Comment,
+    TerminalPrompt,
+    // No style at all
+    Raw,
+}
+
+/// Blocks that need data only known at request time
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DynamicBlock {
+    // "Currently Listening" section filled from LastFM
+    NowPlaying,
+    // `curl` example, only shown to clients that aren't a terminal already
+    CurlHint,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Element {
+    Title {
+        text: String,
+        #[serde(default)]
+        underline: bool,
+    },
+    Subtitle {
+        text: String,
+    },
+    // Always underlined
+    Section {
+        text: String,
+    },
+    SubSection {
+        text: String,
+    },
+    Text {
+        text: String,
+        #[serde(default)]
+        role: Role,
+    },
+    // Urls starting with `/` are made absolute for the requested host
+    Link {
+        text: Option<String>,
+        url: String,
+        #[serde(default = "default_link_role")]
+        role: Role,
+    },
+    Label {
+        label: String,
+        content: Vec<Element>,
+    },
+    CodeBlock {
+        title: Option<String>,
+        language: Option<String>,
+        content: Vec<Element>,
+    },
+    Image {
+        url: String,
+        alt: String,
+        width: i64,
+        height: i64,
+    },
+    // Text file relative to the assets directory
+    File {
+        path: String,
+        #[serde(default)]
+        role: Role,
+    },
+    // Content of another file in the pages directory (by file name without `.toml`)
+    Include {
+        name: String,
+    },
+    Dynamic {
+        name: DynamicBlock,
+    },
+    Footer,
+}
+
+fn default_link_role() -> Role {
+    Role::Link
+}
@@ -1,43 +1,82 @@
 pub mod common;
+pub mod content;
+pub mod readiness;
 pub mod render;
 mod routes;
 
-use crate::config::types::{Config, WebserverConfig};
+use crate::alert::{self, Severity};
+use crate::config::SharedConfig;
+use crate::config::types::WebserverConfig;
 use crate::external::lastfm::LastFM;
+use crate::listener::tls::Tls;
+use crate::listener::{ListenAddress, Listener, UNIX_PEER};
+use crate::logger;
 use crate::maxmind::MaxMind;
 use crate::maxmind::asn::AsnMin;
 use crate::maxmind::city::CityMin;
-use crate::webserver::routes::{canvas, fallback_404, ip, nix, pgp, portfolio, root};
-use crate::webserver::routes::{metrics, now_playing};
+use crate::prometheus::{self, InFlight, RouteLabels};
+use crate::redact;
+use crate::supervisor::Health;
+use crate::webserver::content::SharedPages;
+use crate::webserver::readiness::Readiness;
+use crate::webserver::render::color::ColorDepth;
+use crate::webserver::render::negotiate::{DEFAULT_CLI_USER_AGENTS, FormatOverride, negotiate};
+use crate::webserver::render::{DEFAULT_COLUMNS, RenderFormat, RenderOptions};
+use crate::webserver::routes::{canvas, content_page, fallback_404, health, ip, pgp};
+use crate::webserver::routes::{honeypot, log_filter, metrics, now_playing};
 use anyhow::anyhow;
-use axum::extract::{ConnectInfo, Request, State};
-use axum::http::HeaderMap;
+use axum::body::HttpBody;
+use axum::extract::{ConnectInfo, MatchedPath, Query, Request, State};
+use axum::http::{HeaderMap, Method, Uri, header};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use axum::routing::get;
-use axum::{Router, middleware};
+use axum::serve::ListenerExt;
+use axum::{Extension, Router, ServiceExt, middleware};
+use futures::future::try_join_all;
 use governor::clock::QuantaInstant;
 use governor::middleware::NoOpMiddleware;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::future::IntoFuture;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use tokio::net::TcpListener;
-use tower::ServiceBuilder;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tower::{Layer, ServiceBuilder};
 use tower_governor::governor::{GovernorConfig, GovernorConfigBuilder};
 use tower_governor::key_extractor::KeyExtractor;
 use tower_governor::{GovernorError, GovernorLayer};
 use tower_http::services::{ServeDir, ServeFile};
-use tracing::{error, warn};
+use tracing::field::Empty;
+use tracing::{Instrument, Span, debug, error, info_span, warn};
 
 pub static TERMINAL_PROMPT: &str = "λ ";
 
+/// Routes rendering a Page, these (and the content pages) can also be requested
+/// with a format suffix (`/portfolio.md`)
+const PAGE_ROUTES: &[&str] = &["/pgp", "/canvas", "/health/live", "/health/ready"];
+
+/// Bounds for the `?cols=` query parameter and SSH terminal widths
+pub const MIN_COLUMNS: usize = 20;
+pub const MAX_COLUMNS: usize = 1000;
+
+/// Routes serving files, these take precedence over a format suffix (`/pgp.txt`)
+const FILE_ROUTES: &[&str] = &["/ident.txt", "/pgp.txt", "/favicon.ico"];
+
+/// Set while MaxMind lookups fail, alerts are only sent when this changes
+static MAXMIND_FAILING: AtomicBool = AtomicBool::new(false);
+
 #[derive(Clone)]
 struct WebServerState {
     mm: Arc<MaxMind>,
-    config: Arc<Config>,
+    config: SharedConfig,
     lastfm: Option<Arc<LastFM>>,
+    pages: SharedPages,
+    health: Health,
+    readiness: Arc<Readiness>,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, PartialOrd, Eq, Serialize, Deserialize)]
@@ -53,43 +92,30 @@ fn client_ip(
     remote_addr: SocketAddr,
     config: &ClientIpKeyExtractor,
 ) -> Option<Ident> {
+    // Config::validate makes sure the ip and header are set when behind_X is true, Unix sockets
+    // can only be used behind the proxy, their clients all have the same address
     if config.behind_proxy
-        && remote_addr.ip()
-            == config
-                .proxy_ip
-                .expect("behind_proxy true but proxy_ip not set")
+        && (remote_addr == UNIX_PEER || config.proxy_ip == Some(remote_addr.ip()))
+        && let Some(header) = &config.proxy_header
     {
-        headers
-            .get(
-                config
-                    .proxy_header
-                    .clone()
-                    .expect("trust_proxy_header not set"),
-            )
-            .and_then(|v| {
-                v.to_str().ok().map(String::from).map(|s| Ident {
-                    i2p: false,
-                    data: s.clone(),
-                    ipaddr: s.parse().ok(),
-                })
+        headers.get(header).and_then(|v| {
+            v.to_str().ok().map(String::from).map(|s| Ident {
+                i2p: false,
+                data: s.clone(),
+                ipaddr: s.parse().ok(),
             })
+        })
     } else if config.behind_i2p
-        && remote_addr.ip() == config.i2p_ip.expect("behind_i2p true but i2p_ip not set")
+        && config.i2p_ip == Some(remote_addr.ip())
+        && let Some(header) = &config.i2p_header
     {
-        headers
-            .get(
-                config
-                    .i2p_header
-                    .clone()
-                    .expect("behind_i2p true but i2p_header not set"),
-            )
-            .and_then(|v| {
-                v.to_str().ok().map(String::from).map(|s| Ident {
-                    i2p: true,
-                    data: s,
-                    ipaddr: None,
-                })
+        headers.get(header).and_then(|v| {
+            v.to_str().ok().map(String::from).map(|s| Ident {
+                i2p: true,
+                data: s,
+                ipaddr: None,
             })
+        })
     } else {
         Some(Ident {
             i2p: false,
@@ -113,16 +139,11 @@ impl ClientIpKeyExtractor {
     pub fn new(config: &WebserverConfig) -> Self {
         Self {
             behind_proxy: config.behind_proxy,
-            proxy_ip: config
-                .proxy_ip
-                .clone()
-                .map(|p| p.parse().expect("invalid proxy IP")),
+            // validated by Config::validate, "" means unset
+            proxy_ip: config.proxy_ip.as_deref().and_then(|p| p.parse().ok()),
             proxy_header: config.proxy_header.clone(),
             behind_i2p: config.behind_i2p,
-            i2p_ip: config
-                .i2p_ip
-                .clone()
-                .map(|p| p.parse().expect("invalid I2P IP")),
+            i2p_ip: config.i2p_ip.as_deref().and_then(|p| p.parse().ok()),
             i2p_header: config.i2p_header.clone(),
         }
     }
@@ -146,6 +167,19 @@ impl KeyExtractor for ClientIpKeyExtractor {
     }
 }
 
+/// Builds a ClientIpKeyExtractor from the current config on every request,
+/// so the rate limiter follows config reloads
+#[derive(Clone)]
+pub struct ReloadingKeyExtractor(SharedConfig);
+
+impl KeyExtractor for ReloadingKeyExtractor {
+    type Key = Ident;
+
+    fn extract<T>(&self, req: &Request<T>) -> Result<Self::Key, GovernorError> {
+        ClientIpKeyExtractor::new(&self.0.load().webserver).extract(req)
+    }
+}
+
 async fn api_auth_middleware(
     State(state): State<WebServerState>,
 
@@ -160,7 +194,7 @@ async fn api_auth_middleware(
 
     match token {
         Some(token_value)
-            if token_value == format!("Bearer {}", state.config.webserver.api_token) =>
+            if token_value == format!("Bearer {}", state.config.load().webserver.api_token) =>
         {
             next.run(request).await
         }
@@ -179,6 +213,10 @@ pub struct RequestContext {
     pub ident: Ident,
     pub mm_asn: Option<AsnMin>,
     pub mm_city: Option<CityMin>,
+    pub render: RenderOptions,
+    // the trace of the request's span (trace_middleware), only set while it's exported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
 }
 
 impl RequestContext {
@@ -192,6 +230,141 @@ impl RequestContext {
     }
 }
 
+/// Strips a format suffix from page routes and stores it as FormatOverride
+///
+/// Has to run before routing, so it wraps the whole Router instead of being a layer on it
+async fn format_suffix_middleware(
+    State(state): State<WebServerState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+
+    if !FILE_ROUTES.contains(&path)
+        && let Some((stem, extension)) = path.rsplit_once('.')
+        && let Some(format) = RenderFormat::from_extension(extension)
+    {
+        let stem = if stem == "/index" { "/" } else { stem };
+
+        if PAGE_ROUTES.contains(&stem) || state.pages.load().get(stem).is_some() {
+            let path_and_query = match request.uri().query() {
+                Some(query) => format!("{stem}?{query}"),
+                None => stem.to_string(),
+            };
+
+            let mut parts = request.uri().clone().into_parts();
+            parts.path_and_query = path_and_query.parse().ok();
+
+            match Uri::from_parts(parts) {
+                Ok(uri) => {
+                    *request.uri_mut() = uri;
+                    request.extensions_mut().insert(FormatOverride(format));
+                }
+                Err(e) => warn!("failed to strip format suffix from {path}: {e}"),
+            }
+        }
+    }
+
+    next.run(request).await
+}
+
+/// The matched route, requests no route matched are `unmatched`
+fn route(request: &Request) -> String {
+    let path = request.uri().path();
+    // nested services (the file routes and /static) don't get a MatchedPath
+    match request.extensions().get::<MatchedPath>() {
+        Some(matched) => matched.as_str().to_string(),
+        None if FILE_ROUTES.contains(&path) => path.to_string(),
+        None if path.starts_with("/static/") => "/static".into(),
+        None => "unmatched".into(),
+    }
+}
+
+/// Runs the request in a span, continuing the trace of a `traceparent` header
+///
+/// The outermost layer on the Router, so the span covers the other middlewares
+async fn trace_middleware(request: Request, next: Next) -> Response {
+    let route = route(&request);
+    let span = info_span!(
+        "http_request",
+        otel.name = format!("{} {route}", request.method()),
+        otel.kind = "server",
+        otel.status_code = Empty,
+        http.request.method = %request.method(),
+        http.route = route,
+        http.response.status_code = Empty,
+        client.address = Empty,
+    );
+    logger::set_parent(&span, request.headers());
+
+    let response = next.run(request).instrument(span.clone()).await;
+
+    span.record("http.response.status_code", response.status().as_u16());
+    if response.status().is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    response
+}
+
+/// Records request metrics labelled with the matched route
+///
+/// A layer on the Router, so MatchedPath is set and the rate limiters run inside of it
+async fn metrics_middleware(request: Request, next: Next) -> Response {
+    let labels = RouteLabels {
+        method: method_label(request.method()),
+        route: route(&request),
+    };
+
+    let _in_flight = InFlight::start();
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    // tower_governor sets x-ratelimit-after on the 429s it sends
+    if response.status() == StatusCode::TOO_MANY_REQUESTS
+        && response.headers().contains_key("x-ratelimit-after")
+    {
+        prometheus::inc_http_rate_limited(&labels);
+    }
+    if let Some(format) = response.extensions().get::<RenderFormat>() {
+        prometheus::inc_http_render(&labels.route, format.as_str());
+    }
+
+    let size = response.body().size_hint().exact().or_else(|| {
+        response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+    });
+    prometheus::record_http_request(&labels, response.status().as_u16(), started.elapsed(), size);
+
+    response
+}
+
+/// Unknown methods share one label, so they can't grow the label set
+fn method_label(method: &Method) -> &'static str {
+    match method.as_str() {
+        "GET" => "GET",
+        "HEAD" => "HEAD",
+        "POST" => "POST",
+        "PUT" => "PUT",
+        "PATCH" => "PATCH",
+        "DELETE" => "DELETE",
+        "OPTIONS" => "OPTIONS",
+        _ => "OTHER",
+    }
+}
+
+#[derive(Deserialize)]
+struct RenderQuery {
+    format: Option<String>,
+    cols: Option<String>,
+    colors: Option<String>,
+    // Same values as the COLORTERM environment variable
+    colorterm: Option<String>,
+    hyperlinks: Option<String>,
+}
+
 async fn user_context_middleware(
     State(state): State<WebServerState>,
 
@@ -213,6 +386,60 @@ async fn user_context_middleware(
         .map(|s| s.to_string())
         .unwrap_or_default();
 
+    let accept = headers
+        .get(axum::http::header::ACCEPT)
+        .and_then(|v| v.to_str().ok());
+
+    let render_query = Query::<RenderQuery>::try_from_uri(request.uri())
+        .map(|q| q.0)
+        .ok();
+
+    let explicit_format = render_query
+        .as_ref()
+        .and_then(|q| q.format.as_deref())
+        .and_then(|f| f.parse().ok())
+        .or_else(|| request.extensions().get::<FormatOverride>().map(|f| f.0));
+
+    // ?cols=0 disables wrapping
+    let columns = match render_query
+        .as_ref()
+        .and_then(|q| q.cols.as_deref())
+        .and_then(|c| c.trim().parse::<usize>().ok())
+    {
+        Some(0) => None,
+        Some(columns) => Some(columns.clamp(MIN_COLUMNS, MAX_COLUMNS)),
+        None => Some(DEFAULT_COLUMNS),
+    };
+
+    // ?colors=16|256|truecolor, terminals without a hint are assumed to support truecolor
+    let colors = render_query
+        .as_ref()
+        .and_then(|q| q.colors.as_deref().or(q.colorterm.as_deref()))
+        .and_then(|c| c.parse::<ColorDepth>().ok())
+        .unwrap_or_default();
+
+    let hyperlinks = render_query
+        .as_ref()
+        .and_then(|q| q.hyperlinks.as_deref())
+        .is_some_and(|h| {
+            matches!(
+                h.trim().to_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        });
+
+    let config = state.config.load();
+
+    let format = match config.webserver.cli_user_agents.as_deref() {
+        Some(patterns) => negotiate(explicit_format, accept, &user_agent, patterns),
+        None => negotiate(
+            explicit_format,
+            accept,
+            &user_agent,
+            DEFAULT_CLI_USER_AGENTS,
+        ),
+    };
+
     let https = headers
         .get("X-Forwarded-Proto")
         .and_then(|v| v.to_str().ok())
@@ -222,7 +449,7 @@ async fn user_context_middleware(
     let ident = match client_ip(
         &headers,
         remote_addr,
-        &ClientIpKeyExtractor::new(&state.config.webserver),
+        &ClientIpKeyExtractor::new(&config.webserver),
     ) {
         Some(ip) => ip,
         None => {
@@ -235,13 +462,40 @@ async fn user_context_middleware(
         }
     };
 
+    // on trace_middleware's span, as privacy.mode allows it to be logged
+    let client = if ident.i2p {
+        redact::id(&ident.data)
+    } else {
+        redact::ip(ident.ipaddr).to_string()
+    };
+    Span::current().record("client.address", client);
+
     let (mm_city, mm_asn) = if !ident.i2p
         && let Some(ip) = ident.ipaddr
     {
         let mm = state.mm.lookup(ip);
 
-        if let Err(ref e) = mm {
-            warn!("MaxMind lookup failed for {ip} {e:?}");
+        // every failure is counted by record_lookup, a broken database would flood the log
+        match &mm {
+            Err(e) => {
+                debug!("MaxMind lookup failed: {e:#}");
+                if !MAXMIND_FAILING.swap(true, Ordering::Relaxed) {
+                    // no IP in the alert, it leaves the server
+                    alert::notify(
+                        Severity::Warning,
+                        "MaxMind",
+                        format!("lookups are failing: {e:#}"),
+                    );
+                }
+            }
+            // checked first, most requests shouldn't write to it
+            Ok(_) => {
+                if MAXMIND_FAILING.load(Ordering::Relaxed)
+                    && MAXMIND_FAILING.swap(false, Ordering::Relaxed)
+                {
+                    alert::notify(Severity::Info, "MaxMind", "lookups work again");
+                }
+            }
         }
 
         mm.map(|mm| (mm.city, mm.asn)).unwrap_or_default()
@@ -256,6 +510,13 @@ async fn user_context_middleware(
         ident,
         mm_asn,
         mm_city,
+        render: RenderOptions {
+            format,
+            columns,
+            colors,
+            hyperlinks,
+        },
+        trace_id: logger::trace_id(&Span::current()),
     };
 
     request.extensions_mut().insert(ctx);
@@ -264,32 +525,44 @@ async fn user_context_middleware(
 }
 
 pub fn make_limiter(
-    config: &Config,
+    config: &SharedConfig,
     replenish_after_ms: u64,
     burst_size: u32,
-) -> anyhow::Result<Arc<GovernorConfig<ClientIpKeyExtractor, NoOpMiddleware<QuantaInstant>>>> {
+) -> anyhow::Result<Arc<GovernorConfig<ReloadingKeyExtractor, NoOpMiddleware<QuantaInstant>>>> {
     Ok(Arc::new(
         GovernorConfigBuilder::default()
             .per_millisecond(replenish_after_ms)
             .burst_size(burst_size)
-            .key_extractor(ClientIpKeyExtractor::new(&config.webserver))
+            .key_extractor(ReloadingKeyExtractor(Arc::clone(config)))
             .finish()
             .ok_or(anyhow!("governor init failed"))?,
     ))
 }
 
 pub async fn init_webserver(
-    config: Arc<Config>,
+    config: SharedConfig,
     mm: Arc<MaxMind>,
     lastfm: Option<Arc<LastFM>>,
+    pages: SharedPages,
+    health: Health,
+    shutdown: CancellationToken,
 ) -> anyhow::Result<()> {
     let root_limiter = make_limiter(&config, 500, 10)?;
     let asset_limiter = make_limiter(&config, 500, 20)?;
 
-    let webserver_state = WebServerState { mm, lastfm, config };
-
     let static_dir = env::var("KYBE_BACKEND_STATIC_DIR").unwrap_or("static".into());
 
+    let state_config = Arc::clone(&config);
+    let readiness = Arc::new(Readiness::new(&config.load()));
+    let webserver_state = WebServerState {
+        mm,
+        lastfm,
+        config,
+        pages: pages.clone(),
+        health,
+        readiness,
+    };
+
     let api_auth_layer =
         middleware::from_fn_with_state(webserver_state.clone(), api_auth_middleware);
     let ctx_layer =
@@ -307,11 +580,22 @@ pub async fn init_webserver(
 
     let unlogged_route2 = Router::new()
         .route("/metrics", get(metrics::metrics))
+        .route(
+            "/logger/filter",
+            get(log_filter::filter)
+                .put(log_filter::set_filter)
+                .delete(log_filter::reset_filter),
+        )
+        .route("/honeypot/sessions", get(honeypot::sessions))
+        .route("/honeypot/sessions/{id}/events", get(honeypot::events))
+        .route("/honeypot/sessions/{id}/cast", get(honeypot::cast))
         .layer(api_auth_layer)
         .with_state(webserver_state.clone());
 
     let unlogged_route = Router::new()
-        .route("/health", get(|| async { "OK" }))
+        .route("/health", get(health::live))
+        .route("/health/live", get(health::live))
+        .route("/health/ready", get(health::ready))
         .nest_service(
             "/ident.txt",
             ServeFile::new(format!("{static_dir}/ident.txt")),
@@ -328,27 +612,69 @@ pub async fn init_webserver(
         .layer(asset_limiter_layer.clone());
 
     let api_routes = Router::new()
-        .route("/", get(root::root))
         .route("/ip", get(ip::ip))
         .route("/now", get(now_playing::now_playing))
-        .route("/nix", get(nix::nix))
         .route("/pgp", get(pgp::pgp))
-        .route("/canvas", get(canvas::canvas))
-        .route("/portfolio", get(portfolio::portfolio))
+        .route("/canvas", get(canvas::canvas));
+
+    // Routes can't be added to a running Router, new pages are only served after a restart
+    let api_routes = pages
+        .load()
+        .iter()
+        .fold(api_routes, |router, page| {
+            let route = page.route.clone();
+            router.route(
+                &page.route,
+                get(
+                    move |state: State<WebServerState>, ctx: Extension<RequestContext>| {
+                        content_page::content_page(state, ctx, route.clone())
+                    },
+                ),
+            )
+        })
         .layer(root_route_service);
 
     let app = unlogged_route
         .merge(unlogged_route2)
         .merge(api_routes)
         .fallback(fallback_404::fallback_404)
-        .with_state(webserver_state)
-        .layer(ctx_layer);
-
-    let listener = TcpListener::bind("0.0.0.0:3000").await?;
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await?;
+        .with_state(webserver_state.clone())
+        .layer(ctx_layer)
+        .layer(middleware::from_fn(metrics_middleware))
+        .layer(middleware::from_fn(trace_middleware));
+
+    let app = middleware::from_fn_with_state(webserver_state, format_suffix_middleware).layer(app);
+
+    let webserver = state_config.load().webserver.clone();
+    let tls = webserver
+        .tls
+        .enabled
+        .then(|| Tls::new(&webserver.tls))
+        .transpose()?;
+
+    let mut servers = Vec::new();
+    for address in &webserver.listen {
+        let address: ListenAddress = address
+            .parse()
+            .map_err(|e| anyhow!("webserver.listen: {e}"))?;
+        let listener = Listener::bind(address.clone(), tls.clone())
+            .map_err(|e| anyhow!("failed to bind {address}: {e}"))?
+            // TapIo provides the peer address as ConnectInfo<SocketAddr> for any listener
+            .tap_io(|_| {});
+
+        servers.push(
+            axum::serve(
+                listener,
+                ServiceExt::<Request>::into_make_service_with_connect_info::<SocketAddr>(
+                    app.clone(),
+                ),
+            )
+            // stops accepting and waits for in-flight requests
+            .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+            .into_future(),
+        );
+    }
+
+    try_join_all(servers).await?;
     Ok(())
 }
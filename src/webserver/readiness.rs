@@ -0,0 +1,266 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    config::types::Config,
+    discord_bot,
+    external::lastfm::LastFM,
+    maxmind::MaxMind,
+    ssh,
+    supervisor::{self, Health},
+    translator::Translator,
+};
+
+/// A translator probe is reused for this long, so /health/ready can be polled often
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dependency {
+    MaxmindCity,
+    MaxmindAsn,
+    Lastfm,
+    Discord,
+    Ssh,
+    Translator,
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Dependency::MaxmindCity => "maxmind_city",
+            Dependency::MaxmindAsn => "maxmind_asn",
+            Dependency::Lastfm => "lastfm",
+            Dependency::Discord => "discord",
+            Dependency::Ssh => "ssh",
+            Dependency::Translator => "translator",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckState {
+    Ok,
+    Stale,
+    Down,
+    Disabled,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Check {
+    pub state: CheckState,
+    // a critical dependency which is down or stale fails readiness
+    pub critical: bool,
+    // how old the data is (database build, cache sync, last probe)
+    pub age_secs: Option<u64>,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(state: CheckState, detail: impl Into<String>) -> Self {
+        Self {
+            state,
+            critical: false,
+            age_secs: None,
+            detail: detail.into(),
+        }
+    }
+
+    fn age(mut self, secs: u64) -> Self {
+        self.age_secs = Some(secs);
+        self
+    }
+
+    pub fn failing(&self) -> bool {
+        matches!(self.state, CheckState::Stale | CheckState::Down)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadyState {
+    Ready,
+    // only non critical dependencies are failing
+    Degraded,
+    NotReady,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub status: ReadyState,
+    pub checks: BTreeMap<Dependency, Check>,
+}
+
+/// Checks the dependencies for /health/ready
+pub struct Readiness {
+    // built from the startup config, like the bot's translator
+    translator: Option<Translator>,
+    // the last translator probe and its result
+    probe: Mutex<Option<(Instant, Result<(), String>)>>,
+}
+
+impl Readiness {
+    pub fn new(config: &Config) -> Self {
+        let translator = config
+            .discord_bot
+            .enable
+            .then(|| Translator::try_from(config.discord_bot.translator.clone()).ok())
+            .flatten();
+
+        Self {
+            translator,
+            probe: Mutex::new(None),
+        }
+    }
+
+    pub async fn check(
+        &self,
+        config: &Config,
+        mm: &MaxMind,
+        lastfm: Option<&LastFM>,
+        health: &Health,
+    ) -> Report {
+        let subsystems = health.snapshot();
+        let running = |name: &str| {
+            subsystems
+                .get(name)
+                .is_some_and(|s| s.state == supervisor::State::Running)
+        };
+        let max_age_days = config.health.maxmind_max_age_days;
+
+        let mut checks = BTreeMap::from([
+            (
+                Dependency::MaxmindCity,
+                maxmind_check(mm.city_build_epoch(), max_age_days),
+            ),
+            (
+                Dependency::MaxmindAsn,
+                maxmind_check(mm.asn_build_epoch(), max_age_days),
+            ),
+            (
+                Dependency::Lastfm,
+                lastfm_check(lastfm, config.health.lastfm_max_age_secs).await,
+            ),
+            (Dependency::Translator, self.translator().await),
+        ]);
+
+        checks.insert(
+            Dependency::Discord,
+            if !config.discord_bot.enable {
+                Check::new(CheckState::Disabled, "discord_bot.enable is false")
+            } else if discord_bot::CONNECTED.load(Ordering::Relaxed) && running("Discord Bot") {
+                Check::new(CheckState::Ok, "gateway connected")
+            } else {
+                Check::new(CheckState::Down, "gateway not connected")
+            },
+        );
+
+        checks.insert(
+            Dependency::Ssh,
+            if ssh::LISTENING.load(Ordering::Relaxed) && running("SSH") {
+                Check::new(
+                    CheckState::Ok,
+                    format!("listening on {}", config.ssh.listen.join(", ")),
+                )
+            } else {
+                Check::new(CheckState::Down, "not listening")
+            },
+        );
+
+        for (dependency, check) in &mut checks {
+            check.critical = config.health.critical.contains(dependency);
+        }
+
+        let status = if checks.values().any(|c| c.critical && c.failing()) {
+            ReadyState::NotReady
+        } else if checks.values().any(Check::failing) {
+            ReadyState::Degraded
+        } else {
+            ReadyState::Ready
+        };
+
+        Report { status, checks }
+    }
+
+    async fn translator(&self) -> Check {
+        let Some(translator) = &self.translator else {
+            return Check::new(CheckState::Disabled, "translator not enabled");
+        };
+
+        // held during the probe so concurrent requests wait for its result
+        let mut probe = self.probe.lock().await;
+        let (probed, result) = match &*probe {
+            Some((probed, result)) if probed.elapsed() < PROBE_INTERVAL => {
+                (*probed, result.clone())
+            }
+            _ => {
+                let result = match tokio::time::timeout(PROBE_TIMEOUT, translator.languages()).await
+                {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err(e)) => Err(format!("{e:#}")),
+                    Err(_) => Err(format!("timed out after {}s", PROBE_TIMEOUT.as_secs())),
+                };
+                let probed = Instant::now();
+                *probe = Some((probed, result.clone()));
+                (probed, result)
+            }
+        };
+
+        let check = match result {
+            Ok(()) => Check::new(CheckState::Ok, "reachable"),
+            Err(e) => Check::new(CheckState::Down, e),
+        };
+        check.age(probed.elapsed().as_secs())
+    }
+}
+
+fn maxmind_check(build_epoch: Option<u64>, max_age_days: u64) -> Check {
+    let Some(build_epoch) = build_epoch else {
+        return Check::new(CheckState::Disabled, "not loaded");
+    };
+
+    let now = u64::try_from(Utc::now().timestamp()).unwrap_or_default();
+    let age = now.saturating_sub(build_epoch);
+    let built = i64::try_from(build_epoch)
+        .ok()
+        .and_then(|epoch| DateTime::from_timestamp(epoch, 0))
+        .map(|built| built.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| build_epoch.to_string());
+
+    let check = if age > max_age_days.saturating_mul(86400) {
+        Check::new(
+            CheckState::Stale,
+            format!("built {built}, older than {max_age_days} days"),
+        )
+    } else {
+        Check::new(CheckState::Ok, format!("built {built}"))
+    };
+    check.age(age)
+}
+
+async fn lastfm_check(lastfm: Option<&LastFM>, max_age_secs: u64) -> Check {
+    let Some(lastfm) = lastfm else {
+        return Check::new(CheckState::Disabled, "lastfm not enabled");
+    };
+
+    let age = u64::try_from(lastfm.get_playing().await.sync_age / 1000).unwrap_or(u64::MAX);
+    let check = if age > max_age_secs {
+        Check::new(
+            CheckState::Stale,
+            format!("last sync older than {max_age_secs}s"),
+        )
+    } else {
+        Check::new(CheckState::Ok, "cache fresh")
+    };
+    check.age(age)
+}
@@ -1,3 +1,5 @@
+use unicode_width::UnicodeWidthChar;
+
 use crate::webserver::render::{
     Object, Page, PageRenderer, Style, Theme,
     color::bit4::Bit4Color,
@@ -8,11 +10,22 @@ pub struct AnsiRenderer();
 
 impl<'a> PageRenderer<'a> for AnsiRenderer {
     fn render(page: &Page<'a>) -> String {
-        (*page.objects)
+        let render_object = if page.options.hyperlinks {
+            Self::render_object_hyperlinked
+        } else {
+            Self::render_object
+        };
+
+        let output = (*page.objects)
             .iter()
-            .map(Self::render_object)
+            .map(render_object)
             .collect::<Vec<_>>()
-            .join("")
+            .join("");
+
+        match page.options.columns {
+            Some(columns) => wrap(&output, columns),
+            None => output,
+        }
     }
 
     fn render_object(obj: &Object) -> String {
@@ -180,3 +193,422 @@ impl<'a> PageRenderer<'a> for AnsiRenderer {
         }
     }
 }
+
+impl AnsiRenderer {
+    /// Like render_object, but links become OSC 8 hyperlinks instead of a ` => url` suffix
+    fn render_object_hyperlinked(obj: &Object) -> String {
+        match obj {
+            Object::TextBlob {
+                text,
+                style,
+                link_to: Some(link_to),
+                ..
+            } if link_to.link().starts_with("http") => hyperlink(text, style, link_to),
+            Object::Image { url, alt, .. } => Self::render_object_hyperlinked(
+                &Theme::default().link_colored(alt.as_str(), url).into(),
+            ),
+            _ => Self::render_object(obj),
+        }
+    }
+}
+
+/// Turns the first line of the text into the link, the url itself is only shown
+/// when there is no text to click on
+fn hyperlink(text: &str, style: &Style, link_to: &LinkTo) -> String {
+    let url = link_to
+        .link()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>();
+    let (label, rest) = text.split_at(text.find('\n').unwrap_or(text.len()));
+
+    let (label, label_style) = if label.trim().is_empty() {
+        (url.as_str(), link_to.link_style().unwrap_or(style))
+    } else {
+        (label, style)
+    };
+
+    format!(
+        "{}\x1b]8;;{url}\x1b\\{label}\x1b]8;;\x1b\\{}{rest}{}",
+        label_style.ansi_code(),
+        style.ansi_code(),
+        Style::default().ansi_code()
+    )
+}
+
+/// Either a run of spaces or a word of a rendered line,
+/// escape sequences belong to the unit they follow
+struct Unit {
+    raw: String,
+    // only the escape sequences of raw, they keep the SGR state when raw isn't written
+    escapes: String,
+    width: usize,
+    gap: bool,
+}
+
+/// Length in bytes of the escape sequence `s` starts with (CSI, OSC or a two char sequence)
+fn escape_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    match bytes.get(1) {
+        Some(b'[') => bytes[2..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map(|p| p + 3)
+            .unwrap_or(bytes.len()),
+        Some(b']') => {
+            // terminated by BEL or ST (ESC \)
+            for i in 2..bytes.len() {
+                if bytes[i] == 0x07 {
+                    return i + 1;
+                }
+                if bytes[i] == 0x1b && bytes.get(i + 1) == Some(&b'\\') {
+                    return i + 2;
+                }
+            }
+            bytes.len()
+        }
+        _ => 1 + s[1..].chars().next().map_or(0, char::len_utf8),
+    }
+}
+
+/// `s` as a terminal shows it, without escape sequences
+pub fn strip_escapes(s: &str) -> String {
+    let mut output = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(ch) = rest.chars().next() {
+        let len = if ch == '\x1b' {
+            escape_len(rest)
+        } else {
+            output.push(ch);
+            ch.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    output
+}
+
+fn units(line: &str) -> Vec<Unit> {
+    let mut units: Vec<Unit> = Vec::new();
+    let mut rest = line;
+
+    while let Some(ch) = rest.chars().next() {
+        if ch == '\x1b' {
+            let (escape, tail) = rest.split_at(escape_len(rest));
+            match units.last_mut() {
+                Some(unit) => {
+                    unit.raw.push_str(escape);
+                    unit.escapes.push_str(escape);
+                }
+                None => units.push(Unit {
+                    raw: escape.into(),
+                    escapes: escape.into(),
+                    width: 0,
+                    gap: true,
+                }),
+            }
+            rest = tail;
+            continue;
+        }
+
+        let gap = ch == ' ';
+        let width = ch.width().unwrap_or(0);
+        match units.last_mut() {
+            Some(unit) if unit.gap == gap => {
+                unit.raw.push(ch);
+                unit.width += width;
+            }
+            _ => units.push(Unit {
+                raw: ch.into(),
+                escapes: String::new(),
+                width,
+                gap,
+            }),
+        }
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    units
+}
+
+/// Merges the `=>` of a LinkTo suffix with the link following it
+fn glue_links(units: Vec<Unit>) -> Vec<Unit> {
+    let mut output: Vec<Unit> = Vec::with_capacity(units.len());
+    let mut units = units.into_iter().peekable();
+
+    while let Some(mut unit) = units.next() {
+        if !unit.gap && strip_escapes(&unit.raw) == "=>" {
+            while let Some(next) = units.next_if(|u| u.gap) {
+                unit.raw.push_str(&next.raw);
+                unit.escapes.push_str(&next.escapes);
+                unit.width += next.width;
+            }
+            if let Some(next) = units.next() {
+                unit.raw.push_str(&next.raw);
+                unit.escapes.push_str(&next.escapes);
+                unit.width += next.width;
+            }
+        }
+        output.push(unit);
+    }
+
+    output
+}
+
+/// Cuts rule lines (`===`, `---`, `~~~`) to the width instead of wrapping them
+fn truncate_rule(line: &str, columns: usize) -> Option<String> {
+    let visible = strip_escapes(line);
+    let first = visible.chars().next()?;
+    if !matches!(first, '=' | '-' | '~')
+        || !visible.chars().all(|c| c == first)
+        || visible.chars().count() <= columns
+    {
+        return None;
+    }
+
+    let mut output = String::with_capacity(line.len());
+    let mut left = columns;
+    let mut rest = line;
+    while let Some(ch) = rest.chars().next() {
+        let len = if ch == '\x1b' {
+            let len = escape_len(rest);
+            output.push_str(&rest[..len]);
+            len
+        } else {
+            if left > 0 {
+                output.push(ch);
+                left -= 1;
+            }
+            ch.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    Some(output)
+}
+
+fn wrap_line(line: &str, columns: usize) -> String {
+    if let Some(rule) = truncate_rule(line, columns) {
+        return rule;
+    }
+
+    let mut output = String::with_capacity(line.len());
+    let mut width = 0;
+    let mut has_word = false;
+    let mut pending_gap: Option<Unit> = None;
+    // the SGR state at the end of the output so far
+    let mut sgr = Sgr::default();
+
+    for unit in glue_links(units(line)) {
+        if unit.gap {
+            pending_gap = Some(unit);
+            continue;
+        }
+
+        let gap = pending_gap.take();
+        let gap_width = gap.as_ref().map_or(0, |g| g.width);
+
+        if has_word && width + gap_width + unit.width > columns {
+            if let Some(gap) = gap {
+                output.push_str(&break_gap(&gap, columns.saturating_sub(width), &mut sgr));
+            }
+            output.push('\n');
+            width = 0;
+        } else if let Some(gap) = gap {
+            sgr.apply_all(&gap.escapes);
+            output.push_str(&gap.raw);
+            width += gap.width;
+        }
+
+        sgr.apply_all(&unit.escapes);
+        output.push_str(&unit.raw);
+        width += unit.width;
+        has_word = true;
+    }
+
+    if let Some(gap) = pending_gap {
+        output.push_str(&gap.raw);
+    }
+
+    output
+}
+
+/// A gap which turns into a line break keeps its escapes. If its spaces are colored (a
+/// background or reverse video) the ones that fit stay too, the color would stop early otherwise.
+fn break_gap(gap: &Unit, left: usize, sgr: &mut Sgr) -> String {
+    let mut filled = String::with_capacity(gap.raw.len());
+    let mut colored = false;
+    let mut left = left;
+    let mut rest = gap.raw.as_str();
+    while let Some(ch) = rest.chars().next() {
+        let len = if ch == '\x1b' {
+            let len = escape_len(rest);
+            sgr.apply(&rest[..len]);
+            filled.push_str(&rest[..len]);
+            len
+        } else {
+            if left > 0 {
+                colored |= sgr.visible();
+                filled.push(ch);
+                left -= 1;
+            }
+            ch.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+
+    if colored { filled } else { gap.escapes.clone() }
+}
+
+/// Whether spaces show up colored, following the SGR sequences written so far
+#[derive(Clone, Copy, Default)]
+struct Sgr {
+    background: bool,
+    reverse: bool,
+}
+
+impl Sgr {
+    fn visible(&self) -> bool {
+        self.background || self.reverse
+    }
+
+    fn apply_all(&mut self, escapes: &str) {
+        let mut rest = escapes;
+        while !rest.is_empty() {
+            let len = escape_len(rest);
+            self.apply(&rest[..len]);
+            rest = &rest[len..];
+        }
+    }
+
+    fn apply(&mut self, escape: &str) {
+        let Some(params) = escape
+            .strip_prefix("\x1b[")
+            .and_then(|escape| escape.strip_suffix('m'))
+        else {
+            return;
+        };
+        let mut params = params.split(';');
+        while let Some(param) = params.next() {
+            // sub parameters (`4:3`) only follow attributes which don't color spaces
+            match param.split(':').next().unwrap_or_default() {
+                "" | "0" => *self = Self::default(),
+                "7" => self.reverse = true,
+                "27" => self.reverse = false,
+                "49" => self.background = false,
+                code @ ("38" | "48") => {
+                    self.background |= code == "48";
+                    // 5;n or 2;r;g;b
+                    let skip = match params.next() {
+                        Some("5") => 1,
+                        Some("2") => 3,
+                        _ => 0,
+                    };
+                    params.by_ref().take(skip).for_each(drop);
+                }
+                code => {
+                    if let Ok(code) = code.parse::<u8>()
+                        && matches!(code, 40..=47 | 100..=107)
+                    {
+                        self.background = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Wraps rendered output on word boundaries
+///
+/// Escape sequences don't count towards the width and are never split, a ` => link`
+/// suffix is kept in one piece and words longer than a line are left as they are.
+fn wrap(text: &str, columns: usize) -> String {
+    text.split('\n')
+        .map(|line| wrap_line(line, columns))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_dont_count_towards_the_width() {
+        let line = "\x1b[1maaa\x1b[0m \x1b[32mbb\x1b[0m";
+        assert_eq!(wrap(line, 6), line);
+        assert_eq!(wrap(line, 5), "\x1b[1maaa\x1b[0m\x1b[32m\nbb\x1b[0m");
+    }
+
+    #[test]
+    fn escapes_stay_whole_at_the_boundary() {
+        let line = "aaa\x1b[38;2;1;2;3m bbb";
+        assert_eq!(wrap(line, 3), "aaa\x1b[38;2;1;2;3m\nbbb");
+    }
+
+    #[test]
+    fn hyperlink_escapes_dont_count() {
+        let line = "\x1b]8;;https://a.b\x1b\\aaa\x1b]8;;\x1b\\ bbb";
+        assert_eq!(wrap(line, 7), line);
+        assert_eq!(
+            wrap(line, 6),
+            "\x1b]8;;https://a.b\x1b\\aaa\x1b]8;;\x1b\\\nbbb"
+        );
+    }
+
+    #[test]
+    fn link_suffix_moves_as_one() {
+        let line = "some text \x1b[0m\x1b[90m => \x1b[0m\x1b[34mhttps://kybe.xyz\x1b[0m";
+        assert_eq!(
+            wrap(line, 20),
+            "some text\x1b[0m\x1b[90m\n=> \x1b[0m\x1b[34mhttps://kybe.xyz\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn link_suffix_stays_when_it_fits() {
+        let line = "text => https://a.b";
+        assert_eq!(wrap(line, 19), line);
+        assert_eq!(wrap(line, 18), "text\n=> https://a.b");
+    }
+
+    #[test]
+    fn lines_wrap_separately() {
+        assert_eq!(wrap("aa bb\ncc dd", 5), "aa bb\ncc dd");
+        assert_eq!(wrap("aa bb\ncc dd", 4), "aa\nbb\ncc\ndd");
+    }
+
+    #[test]
+    fn rules_are_truncated() {
+        assert_eq!(wrap("\x1b[2m--------\x1b[0m", 4), "\x1b[2m----\x1b[0m");
+    }
+
+    #[test]
+    fn colored_gap_fills_the_line() {
+        let line = "aaaa\x1b[41m      \x1b[0mbbbb";
+        assert_eq!(wrap(line, 6), "aaaa\x1b[41m  \x1b[0m\nbbbb");
+    }
+
+    #[test]
+    fn plain_gap_keeps_only_escapes() {
+        let line = "aaaa\x1b[31m  \x1b[0mbbbb";
+        assert_eq!(wrap(line, 6), "aaaa\x1b[31m\x1b[0m\nbbbb");
+    }
+
+    #[test]
+    fn extended_foreground_isnt_a_background() {
+        // 38;5;41 is a foreground, 41 there is no background
+        let line = "aaaa\x1b[38;5;41m  \x1b[0mbbbb";
+        assert_eq!(wrap(line, 6), "aaaa\x1b[38;5;41m\x1b[0m\nbbbb");
+    }
+
+    #[test]
+    fn reverse_gap_fills_the_line() {
+        let line = "\x1b[7maaaa    bbbb";
+        assert_eq!(wrap(line, 5), "\x1b[7maaaa \nbbbb");
+    }
+
+    #[test]
+    fn long_word_is_left_as_is() {
+        assert_eq!(wrap("a bbbbbbbbbb c", 4), "a\nbbbbbbbbbb\nc");
+        assert_eq!(wrap("bbbbbbbbbb", 4), "bbbbbbbbbb");
+    }
+}
@@ -15,7 +15,6 @@ impl CodeBlockBuilder {
         }
     }
 
-    #[allow(unused)]
     pub fn language(self, language: impl Into<String>) -> CodeBlockBuilder {
         CodeBlockBuilder {
             title: self.title,
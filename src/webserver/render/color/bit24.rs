@@ -28,6 +28,10 @@ impl Bit24Color {
     pub const fn new(red: u8, green: u8, blue: u8) -> Self {
         Self(Some(Rgb::new(red, green, blue)))
     }
+
+    pub fn rgb(&self) -> Option<Rgb> {
+        self.0
+    }
 }
 
 impl ColorTrait for Bit24Color {
@@ -1,4 +1,4 @@
-use crate::webserver::render::color::{ColorTrait, bit24::Bit24Color};
+use crate::webserver::render::color::{ColorTrait, bit24::Bit24Color, rgb::Rgb};
 
 impl Bit4Code {
     pub fn fg(&self) -> u8 {
@@ -39,9 +39,40 @@ impl Bit4Color {
 
     pub const DEFAULT: Self = Self(None);
 
+    /// All colors ordered by their code
+    pub const ALL: [Self; 16] = [
+        Self::BLACK,
+        Self::RED,
+        Self::GREEN,
+        Self::YELLOW,
+        Self::BLUE,
+        Self::MAGENTA,
+        Self::CYAN,
+        Self::WHITE,
+        Self::BRIGHT_BLACK,
+        Self::BRIGHT_RED,
+        Self::BRIGHT_GREEN,
+        Self::BRIGHT_YELLOW,
+        Self::BRIGHT_BLUE,
+        Self::BRIGHT_MAGENTA,
+        Self::BRIGHT_CYAN,
+        Self::BRIGHT_WHITE,
+    ];
+
     const fn new(code: u8, bit24: Bit24Color) -> Self {
         Self(Some((Bit4Code(code), bit24)))
     }
+
+    pub fn rgb(&self) -> Option<Rgb> {
+        self.0.and_then(|(_code, bit24)| bit24.rgb())
+    }
+
+    pub fn nearest(rgb: Rgb) -> Self {
+        Self::ALL
+            .into_iter()
+            .min_by_key(|c| c.rgb().map_or(u32::MAX, |c| c.distance(&rgb)))
+            .unwrap_or_default()
+    }
 }
 
 impl ColorTrait for Bit4Color {
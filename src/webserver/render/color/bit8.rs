@@ -0,0 +1,151 @@
+use crate::webserver::render::color::{ColorTrait, bit4::Bit4Color, rgb::Rgb};
+
+/// Channel levels of the 6x6x6 color cube (indices 16..=231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// A color of the xterm 256 color palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bit8Color(Option<u8>);
+
+impl Bit8Color {
+    pub const DEFAULT: Self = Self(None);
+
+    pub const fn new(index: u8) -> Self {
+        Self(Some(index))
+    }
+
+    pub fn rgb(&self) -> Option<Rgb> {
+        let index = self.0?;
+        match index {
+            0..=15 => Bit4Color::ALL[index as usize].rgb(),
+            16..=231 => {
+                let index = index - 16;
+                Some(Rgb::new(
+                    CUBE_LEVELS[(index / 36) as usize],
+                    CUBE_LEVELS[(index / 6 % 6) as usize],
+                    CUBE_LEVELS[(index % 6) as usize],
+                ))
+            }
+            232..=255 => {
+                let level = 8 + (index - 232) * 10;
+                Some(Rgb::new(level, level, level))
+            }
+        }
+    }
+
+    /// Nearest color of the cube or the grayscale ramp,
+    /// the first 16 colors are left out as terminals theme them
+    pub fn nearest(rgb: Rgb) -> Self {
+        let cube_index = |value: u8| {
+            CUBE_LEVELS
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, level)| level.abs_diff(value))
+                .map(|(i, _)| i as u8)
+                .unwrap_or_default()
+        };
+        let cube = Self::new(
+            16 + 36 * cube_index(rgb.red()) + 6 * cube_index(rgb.green()) + cube_index(rgb.blue()),
+        );
+
+        let average = (rgb.red() as u16 + rgb.green() as u16 + rgb.blue() as u16) / 3;
+        let gray = Self::new(232 + (average.saturating_sub(3) / 10).min(23) as u8);
+
+        [cube, gray]
+            .into_iter()
+            .min_by_key(|c| c.rgb().map_or(u32::MAX, |c| c.distance(&rgb)))
+            .unwrap_or(cube)
+    }
+}
+
+impl ColorTrait for Bit8Color {
+    fn is_default(&self) -> bool {
+        self.0.is_none()
+    }
+
+    fn hex(&self) -> Option<String> {
+        self.rgb()
+            .map(|rgb| format!("#{:02X}{:02X}{:02X}", rgb.red(), rgb.green(), rgb.blue()))
+    }
+
+    fn ansi(&self, fg: bool) -> Option<String> {
+        self.0
+            .map(|index| format!("\x1b[{};5;{index}m", if fg { 38 } else { 48 }))
+    }
+}
+
+impl Default for Bit8Color {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_colors_map_to_themselves() {
+        for index in 16..=255 {
+            let color = Bit8Color::new(index);
+            assert_eq!(color.rgb().map(Bit8Color::nearest), Some(color));
+        }
+    }
+
+    #[test]
+    fn cube_rounds_to_the_closest_level() {
+        assert_eq!(Bit8Color::nearest(Rgb::new(255, 0, 0)), Bit8Color::new(196));
+        assert_eq!(
+            Bit8Color::nearest(Rgb::new(250, 90, 10)),
+            Bit8Color::new(202)
+        );
+        // halfway between 0 and 95
+        assert_eq!(
+            Bit8Color::nearest(Rgb::new(255, 47, 0)),
+            Bit8Color::new(196)
+        );
+        assert_eq!(
+            Bit8Color::nearest(Rgb::new(255, 48, 0)),
+            Bit8Color::new(202)
+        );
+    }
+
+    #[test]
+    fn grays_use_the_ramp() {
+        assert_eq!(
+            Bit8Color::nearest(Rgb::new(128, 128, 130)),
+            Bit8Color::new(244)
+        );
+        assert_eq!(
+            Bit8Color::nearest(Rgb::new(20, 19, 18)),
+            Bit8Color::new(233)
+        );
+        // the ramp ends before white and black, the cube has both
+        assert_eq!(
+            Bit8Color::nearest(Rgb::new(255, 255, 255)),
+            Bit8Color::new(231)
+        );
+        assert_eq!(Bit8Color::nearest(Rgb::new(0, 0, 0)), Bit8Color::new(16));
+        assert_eq!(
+            Bit8Color::nearest(Rgb::new(250, 250, 250)),
+            Bit8Color::new(231)
+        );
+        assert_eq!(
+            Bit8Color::nearest(Rgb::new(240, 240, 240)),
+            Bit8Color::new(255)
+        );
+    }
+
+    #[test]
+    fn system_colors_are_never_picked() {
+        for value in (0..=255).step_by(5) {
+            for rgb in [
+                Rgb::new(value, 0, 0),
+                Rgb::new(value, value, value),
+                Rgb::new(0, value, 255 - value),
+            ] {
+                assert!(Bit8Color::nearest(rgb).0.is_some_and(|index| index >= 16));
+            }
+        }
+    }
+}
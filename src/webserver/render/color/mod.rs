@@ -1,22 +1,94 @@
-use crate::webserver::render::color::{bit4::Bit4Color, bit24::Bit24Color};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::webserver::render::color::{bit4::Bit4Color, bit8::Bit8Color, bit24::Bit24Color};
 
 pub mod bit24;
 pub mod bit4;
+pub mod bit8;
 pub mod rgb;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[enum_delegate::implement(ColorTrait)]
 pub enum Color {
     Bit24Color(Bit24Color),
+    Bit8(Bit8Color),
     Bit4(Bit4Color),
 }
 
+/// How many colors the client's terminal can show
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorDepth {
+    Bit4,
+    Bit8,
+    #[default]
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Guesses the depth from a TERM value (like the one sent with a SSH pty request)
+    pub fn from_term(term: &str) -> Self {
+        let term = term.to_lowercase();
+        if term.contains("truecolor") || term.contains("24bit") || term.contains("direct") {
+            Self::TrueColor
+        } else if term.contains("256") {
+            Self::Bit8
+        } else {
+            Self::Bit4
+        }
+    }
+}
+
+impl FromStr for ColorDepth {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "16" | "8" | "bit4" => Ok(Self::Bit4),
+            "256" | "bit8" => Ok(Self::Bit8),
+            "truecolor" | "24bit" | "16m" => Ok(Self::TrueColor),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Color {
+    /// Maps the color to the nearest one the terminal can show
+    pub fn downsample(self, depth: ColorDepth) -> Self {
+        let rgb = match self {
+            Color::Bit24Color(c) => c.rgb(),
+            Color::Bit8(c) => c.rgb(),
+            Color::Bit4(_) => return self,
+        };
+
+        match (depth, rgb) {
+            (ColorDepth::TrueColor, _) => self,
+            (ColorDepth::Bit8, _) if matches!(self, Color::Bit8(_)) => self,
+            (ColorDepth::Bit8, Some(rgb)) => Bit8Color::nearest(rgb).into(),
+            (ColorDepth::Bit4, Some(rgb)) => Bit4Color::nearest(rgb).into(),
+            (_, None) => Bit4Color::DEFAULT.into(),
+        }
+    }
+}
+
 impl Default for Color {
     fn default() -> Self {
         Color::Bit4(Bit4Color::DEFAULT)
     }
 }
 
+/// Serialized as a `#RRGGBB` hex string, `null` for the terminal default color
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.hex() {
+            Some(hex) => serializer.serialize_some(&hex),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
 #[enum_delegate::register]
 pub trait ColorTrait {
     fn is_default(&self) -> bool;
@@ -21,4 +21,12 @@ impl Rgb {
     pub fn blue(&self) -> u8 {
         self.blue
     }
+
+    /// Squared distance weighted by how sensitive the eye is to each channel
+    pub fn distance(&self, other: &Rgb) -> u32 {
+        let red = self.red.abs_diff(other.red) as u32;
+        let green = self.green.abs_diff(other.green) as u32;
+        let blue = self.blue.abs_diff(other.blue) as u32;
+        2 * red * red + 4 * green * green + 3 * blue * blue
+    }
 }
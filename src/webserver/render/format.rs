@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// The output format a Page gets rendered to
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    Ansi,
+    #[default]
+    Html,
+    Plain,
+    Markdown,
+    Json,
+}
+
+impl RenderFormat {
+    /// Maps a path suffix (`/portfolio.md`) to a format
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "ansi" => Some(Self::Ansi),
+            "html" => Some(Self::Html),
+            "txt" => Some(Self::Plain),
+            "md" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Whether the output is most likely shown in a terminal
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Ansi | Self::Plain)
+    }
+
+    /// The name used in metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ansi => "ansi",
+            Self::Html => "html",
+            Self::Plain => "plain",
+            Self::Markdown => "markdown",
+            Self::Json => "json",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Ansi | Self::Plain => "text/plain; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+}
+
+impl FromStr for RenderFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ansi" | "color" | "terminal" => Ok(Self::Ansi),
+            "html" => Ok(Self::Html),
+            "plain" | "text" | "txt" | "no_color" => Ok(Self::Plain),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            _ => Err(()),
+        }
+    }
+}
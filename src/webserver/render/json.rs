@@ -0,0 +1,112 @@
+use serde::Serialize;
+use serde_json::json;
+use tracing::error;
+
+use crate::webserver::render::{
+    Object, Page, PageRenderer, Style,
+    object::{ColorMapping, LinkTo},
+};
+
+/// Bumped on every breaking change to the schema below
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Serializes the Object tree of a Page for API consumers
+///
+/// Schema (version 1):
+/// ```text
+/// page      = { "version": 1, "title": string, "objects": [object] }
+/// object    = text_blob | code_block | image | canvas
+/// text_blob = { "type": "text_blob", "text": string, "copyable": bool,
+///               "style": style, "link_to": link_to | null }
+/// code_block= { "type": "code_block", "title": string | null,
+///               "language": string | null, "code": [object] }
+/// image     = { "type": "image", "url": string, "alt": string,
+///               "width": int, "height": int }
+/// canvas    = { "type": "canvas", "data": string,
+///               "color_mapping": { key: color } }
+/// link_to   = { "link": string, "separator_style": style | null,
+///               "link_style": style | null }
+/// style     = { "fg": color, "bg": color, "bold": bool, "dim": bool,
+///               "italic": bool, "underline": underline, "strikethrough": bool,
+///               "reverse": bool, "blink": bool }
+/// underline = "none" | "single" | "double" | "curly" | "dotted" | "dashed"
+/// color     = "#RRGGBB" | null (terminal default)
+/// ```
+/// New fields may be added without bumping the version.
+pub struct JsonRenderer();
+
+#[derive(Serialize)]
+struct JsonPage<'a> {
+    version: u32,
+    title: &'a str,
+    objects: &'a [Object],
+}
+
+fn to_string(value: &impl Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_else(|e| {
+        error!("failed to serialize page: {e}");
+        "null".into()
+    })
+}
+
+impl<'a> PageRenderer<'a> for JsonRenderer {
+    fn render(page: &Page<'a>) -> String {
+        to_string(&JsonPage {
+            version: SCHEMA_VERSION,
+            title: page.title,
+            objects: &page.objects,
+        })
+    }
+
+    fn render_object(obj: &Object) -> String {
+        to_string(obj)
+    }
+
+    fn render_text_blob(
+        text: &str,
+        copyable: &bool,
+        style: &Style,
+        link_to: &Option<LinkTo>,
+    ) -> String {
+        to_string(&json!({
+            "type": "text_blob",
+            "text": text,
+            "copyable": copyable,
+            "style": style,
+            "link_to": link_to,
+        }))
+    }
+
+    fn render_code_block(
+        title: &Option<String>,
+        language: &Option<String>,
+        code: &[Object],
+    ) -> String {
+        to_string(&json!({
+            "type": "code_block",
+            "title": title,
+            "language": language,
+            "code": code,
+        }))
+    }
+
+    fn render_image(url: &str, alt: &str, width: &i64, height: &i64) -> String {
+        to_string(&json!({
+            "type": "image",
+            "url": url,
+            "alt": alt,
+            "width": width,
+            "height": height,
+        }))
+    }
+
+    fn render_canvas(data: &str, color_mapping: &ColorMapping) -> String {
+        to_string(&json!({
+            "type": "canvas",
+            "data": data,
+            "color_mapping": color_mapping
+                .iter()
+                .collect::<std::collections::BTreeMap<_, _>>(),
+        }))
+    }
+}
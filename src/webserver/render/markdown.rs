@@ -0,0 +1,149 @@
+use crate::webserver::render::{
+    Object, Page, PageRenderer, Style,
+    color::ColorTrait,
+    object::{ColorMapping, LinkTo},
+    plain::PlainRenderer,
+};
+
+pub struct MarkdownRenderer();
+
+impl<'a> PageRenderer<'a> for MarkdownRenderer {
+    fn render(page: &Page<'a>) -> String {
+        (*page.objects)
+            .iter()
+            .map(Self::render_object)
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
+    fn render_object(obj: &Object) -> String {
+        match obj {
+            super::Object::TextBlob {
+                text,
+                copyable,
+                style,
+                link_to,
+            } => Self::render_text_blob(text, copyable, style, link_to),
+            super::Object::CodeBlock {
+                title,
+                language,
+                code,
+            } => Self::render_code_block(title, language, code),
+            super::Object::Canvas {
+                data,
+                color_mapping,
+            } => Self::render_canvas(data, color_mapping),
+            super::Object::Image {
+                url,
+                alt,
+                width,
+                height,
+            } => Self::render_image(url, alt, width, height),
+        }
+    }
+
+    fn render_text_blob(
+        text: &str,
+        _copyable: &bool,
+        style: &Style,
+        link_to: &Option<LinkTo>,
+    ) -> String {
+        // Blobs made up of only whitespace and a background color are decoration
+        // (like the flag in the footer) and would turn into indented code blocks
+        if !style.bg.is_default() && text.trim().is_empty() {
+            return String::new();
+        }
+
+        match link_to {
+            Some(link_to) => {
+                let (label, rest) = text.split_at(text.find('\n').unwrap_or(text.len()));
+                let label = if label.trim().is_empty() {
+                    link_to.link()
+                } else {
+                    label
+                };
+                format!(
+                    "{}{}",
+                    link(label, link_to.link()),
+                    hard_breaks(&escape(rest))
+                )
+            }
+            None => hard_breaks(&escape(text)),
+        }
+    }
+
+    fn render_code_block(
+        title: &Option<String>,
+        language: &Option<String>,
+        code: &[Object],
+    ) -> String {
+        let mut output = String::new();
+
+        if let Some(title) = title {
+            output.push_str(&format!("**{}**\n\n", escape(title)));
+        }
+
+        let code = code
+            .iter()
+            .map(PlainRenderer::render_object)
+            .collect::<Vec<_>>()
+            .join("");
+
+        output.push_str(&fenced(&code, language.as_deref().unwrap_or_default()));
+        output.push('\n');
+
+        output
+    }
+
+    fn render_image(url: &str, alt: &str, _width: &i64, _height: &i64) -> String {
+        format!("!{}", link(alt, url))
+    }
+
+    fn render_canvas(data: &str, color_mapping: &ColorMapping) -> String {
+        fenced(&PlainRenderer::render_canvas(data, color_mapping), "")
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(
+            ch,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            output.push('\\');
+        }
+        output.push(ch);
+    }
+    output
+}
+
+/// Markdown joins consecutive lines into one paragraph, the pages are line based though
+fn hard_breaks(text: &str) -> String {
+    text.replace('\n', "  \n")
+}
+
+fn link(text: &str, url: &str) -> String {
+    format!(
+        "[{}]({})",
+        escape(text),
+        url.replace(' ', "%20")
+            .replace('(', "%28")
+            .replace(')', "%29")
+    )
+}
+
+fn fenced(code: &str, language: &str) -> String {
+    let mut longest_run = 0;
+    let mut run = 0;
+    for ch in code.chars() {
+        run = if ch == '`' { run + 1 } else { 0 };
+        longest_run = longest_run.max(run);
+    }
+    let fence = "`".repeat((longest_run + 1).max(3));
+
+    format!(
+        "{fence}{language}\n{}\n{fence}\n",
+        code.trim_end_matches('\n')
+    )
+}
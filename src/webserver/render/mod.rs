@@ -1,33 +1,67 @@
 mod ansi;
 pub mod builders;
 pub mod color;
+mod format;
 mod html;
+pub mod json;
+mod markdown;
+pub mod negotiate;
 pub mod object;
+mod plain;
 mod style;
 mod theme;
 
+pub use ansi::strip_escapes;
 pub use color::Color;
+pub use format::RenderFormat;
 pub use style::Style;
 pub use theme::Theme;
 
+use axum::{
+    Extension,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
 use crate::{
     config::types::Config,
     webserver::render::{
         ansi::AnsiRenderer,
+        color::ColorDepth,
         html::HtmlRenderer,
+        json::JsonRenderer,
+        markdown::MarkdownRenderer,
         object::{ColorMapping, LinkTo, Object, Objects},
+        plain::PlainRenderer,
     },
 };
 
+/// Terminal width used when a client didn't tell us its width
+pub const DEFAULT_COLUMNS: usize = 80;
+
+/// Everything about the client that changes how a page is rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderOptions {
+    pub format: RenderFormat,
+    // Terminal width to wrap ANSI output at, None disables wrapping
+    pub columns: Option<usize>,
+    // Colors the terminal can show, ANSI output gets downsampled to it
+    pub colors: ColorDepth,
+    // Emit OSC 8 hyperlinks instead of ` => url` suffixes in ANSI output
+    pub hyperlinks: bool,
+}
+
 pub struct Page<'a> {
     title: &'a str,
     config: &'a Config,
     objects: Vec<Object>,
+    options: RenderOptions,
 }
 
 pub struct RenderResult {
     data: String,
-    content_type: String,
+    format: RenderFormat,
 }
 
 pub trait PageRenderer<'a> {
@@ -49,25 +83,47 @@ pub trait PageRenderer<'a> {
 }
 
 impl RenderResult {
-    pub fn new(page: Page, user_agent: &str) -> RenderResult {
-        match user_agent_is_cli(user_agent) {
-            true => RenderResult {
-                data: AnsiRenderer::render(&page),
-                content_type: "text/:-)".into(),
-            },
-            false => RenderResult {
-                data: HtmlRenderer::render(&page),
-                content_type: "text/html".into(),
-            },
+    pub fn new(mut page: Page, options: RenderOptions) -> RenderResult {
+        page.options = options;
+
+        let format = options.format;
+        if format == RenderFormat::Ansi {
+            page.objects
+                .iter_mut()
+                .for_each(|o| o.downsample(options.colors));
         }
+
+        let data = match format {
+            RenderFormat::Ansi => AnsiRenderer::render(&page),
+            RenderFormat::Html => HtmlRenderer::render(&page),
+            RenderFormat::Plain => PlainRenderer::render(&page),
+            RenderFormat::Markdown => MarkdownRenderer::render(&page),
+            RenderFormat::Json => JsonRenderer::render(&page),
+        };
+
+        RenderResult { data, format }
     }
+}
 
-    pub fn take_content_type(&mut self) -> String {
-        std::mem::take(&mut self.content_type)
+impl RenderResult {
+    /// The rendered page, for output which isn't a HTTP response
+    pub fn into_data(self) -> String {
+        self.data
     }
+}
 
-    pub fn take_data(&mut self) -> String {
-        std::mem::take(&mut self.data)
+impl IntoResponse for RenderResult {
+    fn into_response(self) -> Response {
+        (
+            [
+                (header::CONTENT_TYPE, self.format.content_type()),
+                (header::VARY, negotiate::VARY),
+            ],
+            // picked up by the metrics middleware
+            Extension(self.format),
+            self.data,
+        )
+            .into_response()
     }
 }
 
@@ -77,11 +133,12 @@ impl<'a> Page<'a> {
             title,
             config,
             objects,
+            options: RenderOptions::default(),
         }
     }
 
-    pub fn render(self, user_agent: &str) -> RenderResult {
-        RenderResult::new(self, user_agent)
+    pub fn render(self, options: RenderOptions) -> RenderResult {
+        RenderResult::new(self, options)
     }
 }
 
@@ -94,7 +151,3 @@ impl<'a> Page<'a> {
         Self::new(title, config, objects)
     }
 }
-
-pub fn user_agent_is_cli(user_agent: &str) -> bool {
-    user_agent.to_lowercase().trim().contains("curl")
-}
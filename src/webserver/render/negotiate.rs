@@ -0,0 +1,222 @@
+use crate::webserver::render::RenderFormat;
+
+/// Used when `webserver.cli_user_agents` is not set
+pub const DEFAULT_CLI_USER_AGENTS: &[&str] =
+    &["curl", "wget", "httpie", "xh/", "powershell", "fetch"];
+
+/// Header names the negotiated format depends on (for the Vary header)
+pub const VARY: &str = "Accept, User-Agent";
+
+/// Format explicitly requested through a path suffix like `/portfolio.md`,
+/// inserted into the request extensions before routing
+#[derive(Clone, Copy, Debug)]
+pub struct FormatOverride(pub RenderFormat);
+
+struct MediaRange<'a> {
+    kind: &'a str,
+    subtype: &'a str,
+    q: f32,
+}
+
+impl MediaRange<'_> {
+    /// `type/subtype` beats `type/*` beats `*/*` when the q-values are equal
+    fn specificity(&self) -> u8 {
+        match (self.kind, self.subtype) {
+            ("*", _) => 0,
+            (_, "*") => 1,
+            _ => 2,
+        }
+    }
+}
+
+fn parse_accept(accept: &str) -> impl Iterator<Item = MediaRange<'_>> {
+    accept.split(',').filter_map(|range| {
+        let mut params = range.split(';');
+        let (kind, subtype) = params.next()?.trim().split_once('/')?;
+
+        let q = params
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0);
+
+        Some(MediaRange {
+            kind: kind.trim(),
+            subtype: subtype.trim(),
+            q,
+        })
+    })
+}
+
+pub fn user_agent_is_cli<S: AsRef<str>>(user_agent: &str, patterns: &[S]) -> bool {
+    let user_agent = user_agent.to_lowercase();
+    patterns
+        .iter()
+        .any(|p| user_agent.contains(&p.as_ref().to_lowercase()))
+}
+
+/// Picks the format a page gets rendered in
+///
+/// 1. an explicit override (`?format=` or a path suffix) always wins
+/// 2. the Accept header, weighted by q-values
+/// 3. the User-Agent for wildcards, `text/plain` and a missing Accept header
+///    (CLI clients get ANSI, everything else HTML)
+pub fn negotiate<S: AsRef<str>>(
+    explicit: Option<RenderFormat>,
+    accept: Option<&str>,
+    user_agent: &str,
+    cli_user_agents: &[S],
+) -> RenderFormat {
+    if let Some(format) = explicit {
+        return format;
+    }
+
+    let is_cli = user_agent_is_cli(user_agent, cli_user_agents);
+    let fallback = if is_cli {
+        RenderFormat::Ansi
+    } else {
+        RenderFormat::Html
+    };
+
+    let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+        return fallback;
+    };
+
+    let mut best: Option<(f32, u8, RenderFormat)> = None;
+
+    for range in parse_accept(accept) {
+        if range.q <= 0.0 {
+            continue;
+        }
+
+        let format = match (
+            range.kind.to_lowercase().as_str(),
+            range.subtype.to_lowercase().as_str(),
+        ) {
+            ("text", "html") | ("application", "xhtml+xml") => RenderFormat::Html,
+            ("text", "markdown") | ("text", "x-markdown") => RenderFormat::Markdown,
+            ("application", "json") => RenderFormat::Json,
+            // colored output is still plain text for a terminal
+            ("text", "plain") if is_cli => RenderFormat::Ansi,
+            ("text", "plain") => RenderFormat::Plain,
+            ("text", "*") | ("*", "*") => fallback,
+            _ => continue,
+        };
+
+        let specificity = range.specificity();
+        if best.is_none_or(|(q, s, _)| range.q > q || (range.q == q && specificity > s)) {
+            best = Some((range.q, specificity, format));
+        }
+    }
+
+    best.map(|(_, _, format)| format).unwrap_or(fallback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+    const CURL: &str = "curl/8.9.1";
+
+    fn pick(accept: Option<&str>, user_agent: &str) -> RenderFormat {
+        negotiate(None, accept, user_agent, DEFAULT_CLI_USER_AGENTS)
+    }
+
+    #[test]
+    fn explicit_override_wins() {
+        let format = negotiate(
+            Some(RenderFormat::Json),
+            Some("text/html"),
+            FIREFOX,
+            DEFAULT_CLI_USER_AGENTS,
+        );
+        assert_eq!(format, RenderFormat::Json);
+    }
+
+    #[test]
+    fn q_zero_is_never_picked() {
+        assert_eq!(
+            pick(Some("application/json;q=0, text/markdown;q=0.1"), FIREFOX),
+            RenderFormat::Markdown
+        );
+        assert_eq!(pick(Some("text/markdown;q=0"), FIREFOX), RenderFormat::Html);
+        assert_eq!(pick(Some("text/markdown; q=0.0"), CURL), RenderFormat::Ansi);
+    }
+
+    #[test]
+    fn higher_q_wins() {
+        assert_eq!(
+            pick(Some("text/html;q=0.5, application/json"), FIREFOX),
+            RenderFormat::Json
+        );
+        assert_eq!(
+            pick(Some("text/html, application/json;q=0.9"), FIREFOX),
+            RenderFormat::Html
+        );
+    }
+
+    #[test]
+    fn invalid_q_is_clamped() {
+        assert_eq!(
+            pick(Some("text/markdown;q=7, application/json"), FIREFOX),
+            RenderFormat::Markdown
+        );
+        assert_eq!(
+            pick(Some("application/json;q=-1"), FIREFOX),
+            RenderFormat::Html
+        );
+    }
+
+    #[test]
+    fn specific_beats_wildcard_at_equal_q() {
+        assert_eq!(
+            pick(Some("*/*, application/json"), CURL),
+            RenderFormat::Json
+        );
+        assert_eq!(
+            pick(Some("text/*;q=0.8, text/markdown;q=0.8"), FIREFOX),
+            RenderFormat::Markdown
+        );
+    }
+
+    #[test]
+    fn wildcards_follow_the_user_agent() {
+        assert_eq!(pick(Some("*/*"), CURL), RenderFormat::Ansi);
+        assert_eq!(pick(Some("*/*"), FIREFOX), RenderFormat::Html);
+        assert_eq!(pick(Some("text/*"), CURL), RenderFormat::Ansi);
+    }
+
+    #[test]
+    fn text_plain_is_ansi_for_cli_clients() {
+        assert_eq!(pick(Some("text/plain"), CURL), RenderFormat::Ansi);
+        assert_eq!(pick(Some("text/plain"), FIREFOX), RenderFormat::Plain);
+    }
+
+    #[test]
+    fn missing_or_unknown_accept_falls_back() {
+        assert_eq!(pick(None, CURL), RenderFormat::Ansi);
+        assert_eq!(pick(Some(" "), FIREFOX), RenderFormat::Html);
+        assert_eq!(pick(Some("image/png"), CURL), RenderFormat::Ansi);
+        assert_eq!(pick(Some("garbage"), FIREFOX), RenderFormat::Html);
+    }
+
+    #[test]
+    fn media_types_are_case_insensitive() {
+        assert_eq!(pick(Some("Application/JSON"), FIREFOX), RenderFormat::Json);
+    }
+
+    #[test]
+    fn user_agent_patterns() {
+        assert!(user_agent_is_cli("Wget/1.21.4", DEFAULT_CLI_USER_AGENTS));
+        assert!(user_agent_is_cli("xh/0.22.0", DEFAULT_CLI_USER_AGENTS));
+        assert!(user_agent_is_cli(
+            "Mozilla/5.0 (Windows NT; Windows NT 10.0) WindowsPowerShell/5.1",
+            DEFAULT_CLI_USER_AGENTS
+        ));
+        assert!(!user_agent_is_cli(FIREFOX, DEFAULT_CLI_USER_AGENTS));
+        assert!(!user_agent_is_cli("", DEFAULT_CLI_USER_AGENTS));
+        assert!(user_agent_is_cli("my-bot/1", &["BOT"]));
+        assert!(!user_agent_is_cli(CURL, &[] as &[&str]));
+    }
+}
@@ -1,9 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::webserver::render::{Color, Style};
+use serde::{Serialize, Serializer};
+
+use crate::webserver::render::{Color, Style, color::ColorDepth};
 
 pub type ColorMapping = HashMap<String, Color>;
 
+/// Serializes a ColorMapping sorted by key so the output is stable
+fn sorted_color_mapping<S: Serializer>(
+    mapping: &ColorMapping,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    mapping
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .serialize(serializer)
+}
+
+#[derive(Serialize)]
 pub struct LinkTo {
     link: String,
     separator_style: Option<Style>,
@@ -31,6 +45,8 @@ impl LinkTo {
     }
 }
 
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Object {
     TextBlob {
         text: String,
@@ -51,10 +67,34 @@ pub enum Object {
     },
     Canvas {
         data: String,
+        #[serde(serialize_with = "sorted_color_mapping")]
         color_mapping: ColorMapping,
     },
 }
 
+impl Object {
+    /// Maps every color to the nearest one the terminal can show
+    pub fn downsample(&mut self, depth: ColorDepth) {
+        match self {
+            Object::TextBlob { style, link_to, .. } => {
+                style.downsample(depth);
+                if let Some(link_to) = link_to {
+                    link_to
+                        .separator_style
+                        .iter_mut()
+                        .chain(link_to.link_style.iter_mut())
+                        .for_each(|s| s.downsample(depth));
+                }
+            }
+            Object::CodeBlock { code, .. } => code.iter_mut().for_each(|o| o.downsample(depth)),
+            Object::Image { .. } => {}
+            Object::Canvas { color_mapping, .. } => color_mapping
+                .values_mut()
+                .for_each(|c| *c = c.downsample(depth)),
+        }
+    }
+}
+
 pub enum Objects {
     One(Object),
     Many(Vec<Object>),
@@ -0,0 +1,150 @@
+use crate::webserver::render::{
+    Object, Page, PageRenderer, Style,
+    color::ColorTrait,
+    object::{ColorMapping, LinkTo},
+};
+
+/// Same layout as the AnsiRenderer but without any escape codes
+/// (for NO_COLOR users, screen readers and piping into other tools)
+pub struct PlainRenderer();
+
+impl<'a> PageRenderer<'a> for PlainRenderer {
+    fn render(page: &Page<'a>) -> String {
+        (*page.objects)
+            .iter()
+            .map(Self::render_object)
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
+    fn render_object(obj: &Object) -> String {
+        match obj {
+            super::Object::TextBlob {
+                text,
+                copyable,
+                style,
+                link_to,
+            } => Self::render_text_blob(text, copyable, style, link_to),
+            super::Object::CodeBlock {
+                title,
+                language,
+                code,
+            } => Self::render_code_block(title, language, code),
+            super::Object::Canvas {
+                data,
+                color_mapping,
+            } => Self::render_canvas(data, color_mapping),
+            super::Object::Image {
+                url,
+                alt,
+                width,
+                height,
+            } => Self::render_image(url, alt, width, height),
+        }
+    }
+
+    fn render_text_blob(
+        text: &str,
+        _copyable: &bool,
+        _style: &Style,
+        link_to: &Option<LinkTo>,
+    ) -> String {
+        let mut text = text.to_string();
+
+        if let Some(link_to) = link_to
+            && link_to.link().starts_with("http")
+            && !text.contains(link_to.link())
+        {
+            let suffix = format!(" => {}", link_to.link());
+
+            match text.find("\n") {
+                Some(index) => text.insert_str(index, &suffix),
+                None => text.push_str(&suffix),
+            };
+        }
+
+        text
+    }
+
+    fn render_code_block(
+        title: &Option<String>,
+        language: &Option<String>,
+        code: &[Object],
+    ) -> String {
+        let mut output = String::new();
+
+        if title.is_some() || language.is_some() {
+            output.push_str("---title---\n");
+            if let Some(title) = &title {
+                output.push_str(&format!(
+                    "title: {}{}",
+                    title,
+                    if language.is_some() { "," } else { "" }
+                ));
+            }
+            if let Some(language) = language {
+                if title.is_some() {
+                    output.push(' ');
+                }
+                output.push_str(&format!("lang: {}", language));
+            };
+            output.push('\n');
+            output.push_str("---code----\n");
+        } else {
+            output.push_str("\n---code----\n");
+        }
+
+        output.push_str(
+            &code
+                .iter()
+                .map(Self::render_object)
+                .collect::<Vec<_>>()
+                .join(""),
+        );
+
+        output.push_str("\n-----------\n\n");
+
+        output
+    }
+
+    fn render_image(url: &str, alt: &str, _width: &i64, _height: &i64) -> String {
+        format!("{alt} => {url}")
+    }
+
+    /// Without colors the only thing left of a canvas is its shape,
+    /// so every colored cell becomes a full block
+    fn render_canvas(data: &str, color_mapping: &ColorMapping) -> String {
+        let mut output = String::new();
+        let mut buffer = String::new();
+
+        let mut failed = false;
+
+        for ch in data.chars() {
+            buffer.push(ch);
+
+            if buffer == "NL" {
+                output.push('\n');
+                buffer.clear();
+                continue;
+            }
+
+            if let Some(color) = color_mapping.get(&buffer) {
+                output.push(if color.is_default() { ' ' } else { '█' });
+                buffer.clear();
+                continue;
+            }
+
+            let max_key_len = color_mapping.keys().map(|k| k.len()).max().unwrap_or(0);
+            if buffer.len() > max_key_len {
+                failed = true;
+                break;
+            }
+        }
+
+        if !buffer.is_empty() && failed {
+            "Error rendering Canvas".into()
+        } else {
+            output
+        }
+    }
+}
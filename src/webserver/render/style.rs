@@ -1,14 +1,63 @@
+use serde::Serialize;
+
 use crate::webserver::render::{
     Color,
-    color::{ColorTrait, bit4::Bit4Color},
+    color::{ColorDepth, ColorTrait, bit4::Bit4Color},
 };
 
-#[derive(Debug, Clone, Default)]
+/// Text and background color of assets/template.html, used to reverse default colors
+const HTML_DEFAULT_FG: &str = "#ffffff";
+const HTML_DEFAULT_BG: &str = "#0a1026";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(unused)]
+pub enum Underline {
+    #[default]
+    None,
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
+impl Underline {
+    /// Kitty style sub parameters (`4:3`), terminals without support fall back to a single underline
+    fn sgr(&self) -> Option<&'static str> {
+        match self {
+            Underline::None => None,
+            Underline::Single => Some("4"),
+            Underline::Double => Some("4:2"),
+            Underline::Curly => Some("4:3"),
+            Underline::Dotted => Some("4:4"),
+            Underline::Dashed => Some("4:5"),
+        }
+    }
+
+    fn css(&self) -> Option<&'static str> {
+        match self {
+            Underline::None => None,
+            Underline::Single => Some("solid"),
+            Underline::Double => Some("double"),
+            Underline::Curly => Some("wavy"),
+            Underline::Dotted => Some("dotted"),
+            Underline::Dashed => Some("dashed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Style {
     pub fg: Color,
     pub bg: Color,
     pub bold: bool,
     pub dim: bool,
+    pub italic: bool,
+    pub underline: Underline,
+    pub strikethrough: bool,
+    pub reverse: bool,
+    pub blink: bool,
 }
 
 impl Style {
@@ -18,6 +67,11 @@ impl Style {
             bg: Color::Bit4(Bit4Color::DEFAULT),
             bold: false,
             dim: false,
+            italic: false,
+            underline: Underline::None,
+            strikethrough: false,
+            reverse: false,
+            blink: false,
         }
     }
 
@@ -40,34 +94,88 @@ impl Style {
         self.dim = dim;
         self
     }
+
+    #[allow(unused)]
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = italic;
+        self
+    }
+
+    #[allow(unused)]
+    pub fn underline(mut self, underline: Underline) -> Self {
+        self.underline = underline;
+        self
+    }
+
+    #[allow(unused)]
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = strikethrough;
+        self
+    }
+
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    #[allow(unused)]
+    pub fn blink(mut self, blink: bool) -> Self {
+        self.blink = blink;
+        self
+    }
+
+    pub fn downsample(&mut self, depth: ColorDepth) {
+        self.fg = self.fg.downsample(depth);
+        self.bg = self.bg.downsample(depth);
+    }
 }
 
 // Ansi
 impl Style {
     pub fn ansi_code(&self) -> String {
         let reset = "\x1b[0m";
-        let bold = if self.bold { "\x1b[2m" } else { "" };
-        let dim = if self.dim { "\x1b[3m" } else { "" };
+
+        let attributes = [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.blink, "5"),
+            (self.reverse, "7"),
+            (self.strikethrough, "9"),
+        ]
+        .into_iter()
+        .filter_map(|(set, code)| set.then_some(code))
+        .chain(self.underline.sgr())
+        .collect::<Vec<_>>();
+
+        let attributes = if attributes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", attributes.join(";"))
+        };
 
         let fg = self.fg.ansi_fg().unwrap_or_default();
         let bg = self.bg.ansi_bg().unwrap_or_default();
 
-        format!("{reset}{bold}{dim}{fg}{bg}")
+        format!("{reset}{attributes}{fg}{bg}")
     }
 }
 
 // HTML
 impl Style {
     pub fn html_style(&self) -> String {
-        let fg_html = self.fg.html();
-        let bg_html = self.bg.html();
-
-        let mut styles = Vec::with_capacity(
-            self.bold as usize
-                + self.dim as usize
-                + fg_html.is_some() as usize
-                + bg_html.is_some() as usize,
-        );
+        let (fg_html, bg_html) = if self.reverse {
+            let swapped =
+                |color: &Color, default: &str| Some(color.hex().unwrap_or(default.into()));
+            (
+                swapped(&self.bg, HTML_DEFAULT_BG),
+                swapped(&self.fg, HTML_DEFAULT_FG),
+            )
+        } else {
+            (self.fg.html(), self.bg.html())
+        };
+
+        let mut styles = Vec::new();
 
         if self.bold {
             styles.push("font-weight:bold".into());
@@ -77,6 +185,31 @@ impl Style {
             styles.push("opacity:0.6".into());
         }
 
+        if self.italic {
+            styles.push("font-style:italic".into());
+        }
+
+        let decorations = [
+            self.underline.css().map(|_| "underline"),
+            self.strikethrough.then_some("line-through"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        if !decorations.is_empty() {
+            styles.push(format!("text-decoration-line:{}", decorations.join(" ")));
+        }
+
+        if let Some(underline) = self.underline.css() {
+            styles.push(format!("text-decoration-style:{underline}"));
+        }
+
+        if self.blink {
+            // keyframes are defined in assets/template.html
+            styles.push("animation:blink 1s step-end infinite".into());
+        }
+
         if let Some(fg_html) = fg_html {
             styles.push(format!("color:{fg_html}"));
         }
@@ -1,3 +1,5 @@
+use unicode_width::UnicodeWidthStr;
+
 use crate::webserver::render::{
     Object, Style,
     builders::{LinkToBuilder, TextBlobBuilder},
@@ -49,7 +51,7 @@ impl Theme {
             TextBlobBuilder::new(format!("{text}\n"))
                 .style(self.title.clone())
                 .into(),
-            TextBlobBuilder::new("=".repeat(text.len()))
+            TextBlobBuilder::new("=".repeat(text.width()))
                 .style(self.title.clone())
                 .into(),
             TextBlobBuilder::new("\n\n").into(),
@@ -67,7 +69,7 @@ impl Theme {
             TextBlobBuilder::new(format!("{text}\n"))
                 .style(self.section.clone())
                 .into(),
-            TextBlobBuilder::new("-".repeat(text.len()))
+            TextBlobBuilder::new("-".repeat(text.width()))
                 .style(self.section.clone())
                 .into(),
             TextBlobBuilder::new("\n\n").into(),
@@ -85,7 +87,7 @@ impl Theme {
             TextBlobBuilder::new(format!("{text}\n"))
                 .style(self.sub_section.clone())
                 .into(),
-            TextBlobBuilder::new("~".repeat(text.len()))
+            TextBlobBuilder::new("~".repeat(text.width()))
                 .style(self.sub_section.clone())
                 .into(),
             TextBlobBuilder::new("\n\n").into(),
@@ -97,6 +99,7 @@ impl Theme {
         text.into().style(self.subtitle.clone())
     }
 
+    #[allow(unused)]
     pub fn text(&self, text: impl Into<TextBlobBuilder>) -> TextBlobBuilder {
         text.into().style(self.text.clone())
     }
@@ -120,7 +123,6 @@ impl Theme {
         )
     }
 
-    #[allow(unused)]
     pub fn link(&self, text: impl Into<TextBlobBuilder>, link: &str) -> TextBlobBuilder {
         text.into().link_to(
             LinkToBuilder::new(link)
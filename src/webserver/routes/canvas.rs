@@ -1,20 +1,12 @@
 use axum::{
     Extension,
     extract::{Query, State},
-    http::header,
     response::IntoResponse,
 };
 use reqwest::StatusCode;
 use serde::Deserialize;
 
-use crate::webserver::{
-    RequestContext, WebServerState,
-    render::{
-        Page, Style,
-        builders::{COLOR_MAPPING, CanvasBuilder, TextBlobBuilder},
-        color::bit4::Bit4Color,
-    },
-};
+use crate::webserver::{RequestContext, WebServerState, common, render::Page};
 
 #[derive(Deserialize)]
 pub struct CanvasParameters {
@@ -26,42 +18,10 @@ pub async fn canvas(
     Query(parsed_query): Query<CanvasParameters>,
     Extension(ctx): Extension<RequestContext>,
 ) -> impl IntoResponse {
-    let q = parsed_query.q.clone();
-    let page = match q {
-        Some(q) => vec![
-            CanvasBuilder::new(q).into(),
-            TextBlobBuilder::new("\n").style(Style::new()).into(),
-        ],
-        None => {
-            let mut list = COLOR_MAPPING
-                .iter()
-                .map(|(key, value)| format!("{key}: {value:?}"))
-                .collect::<Vec<_>>();
-            list.push("NL: NewLine".into());
-            vec![
-				TextBlobBuilder::new("Canvas\n\n")
-					.style(Style::new().fg(Bit4Color::RED))
-					.into(),
-				TextBlobBuilder::new("Use the q query parameter to use this canvas api\n\n").into(),
-				TextBlobBuilder::new(list.join("\n"))
-					.style(Style::new().fg(Bit4Color::YELLOW))
-					.into(),
-				TextBlobBuilder::new(
-					"\n\nExample: https://kybe.xyz/canvas?q=BLBLBLBLBLBLBLBLBLBLNLRRRRRRRRRRNLYYYYYYYYYY\n",
-				)
-				.into(),
-			]
-        }
-    };
-
-    let page = Page::from_iter("/dev/canvas", &state.config, page);
+    let page = common::canvas::canvas(parsed_query.q);
 
-    let mut result = page.render(&ctx.user_agent);
+    let config = state.config.load();
+    let page = Page::from_iter("/dev/canvas", &config, page);
 
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, result.take_content_type())],
-        result.take_data(),
-    )
-        .into_response()
+    (StatusCode::OK, page.render(ctx.render)).into_response()
 }
@@ -0,0 +1,35 @@
+use axum::{Extension, extract::State, response::IntoResponse};
+use reqwest::StatusCode;
+
+use crate::webserver::{
+    RequestContext, WebServerState,
+    content::{DynamicData, types::DynamicBlock},
+    render::Page,
+    routes::fallback_404,
+};
+
+pub async fn content_page(
+    State(state): State<WebServerState>,
+    Extension(ctx): Extension<RequestContext>,
+    route: String,
+) -> impl IntoResponse {
+    // the page may have been removed by a reload
+    let Some(page) = state.pages.load().get(&route).cloned() else {
+        return fallback_404::fallback_404(State(state), Extension(ctx))
+            .await
+            .into_response();
+    };
+
+    let mut data = DynamicData::default();
+
+    if page.uses(DynamicBlock::NowPlaying)
+        && let Some(lastfm) = &state.lastfm
+    {
+        data.playing = lastfm.get_playing().await.result;
+    }
+
+    let config = state.config.load();
+    let page = Page::new(&page.title, &config, page.objects(&ctx, &data));
+
+    (StatusCode::OK, page.render(ctx.render)).into_response()
+}
@@ -0,0 +1,220 @@
+use axum::{
+    Extension, Json,
+    extract::State,
+    response::{IntoResponse, Response},
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    supervisor,
+    webserver::{
+        RequestContext, WebServerState,
+        readiness::{CheckState, ReadyState},
+        render::{
+            Page, RenderFormat, Style, Theme,
+            builders::{CodeBlockBuilder, TextBlobBuilder},
+            color::bit4::Bit4Color,
+            object::Object,
+        },
+    },
+};
+
+/// 200 while all critical subsystems run (`degraded` if others are restarting), 503 otherwise
+pub async fn live(
+    State(state): State<WebServerState>,
+    Extension(ctx): Extension<RequestContext>,
+) -> Response {
+    let subsystems = state.health.snapshot();
+    let running = |s: &&supervisor::Status| s.state == supervisor::State::Running;
+
+    let (status, code) = if subsystems
+        .values()
+        .filter(|s| s.critical)
+        .all(|s| running(&s))
+    {
+        if subsystems.values().all(|s| running(&s)) {
+            ("ok", StatusCode::OK)
+        } else {
+            ("degraded", StatusCode::OK)
+        }
+    } else {
+        ("failing", StatusCode::SERVICE_UNAVAILABLE)
+    };
+
+    if ctx.render.format == RenderFormat::Json {
+        return (
+            code,
+            Json(json!({
+                "status": status,
+                "subsystems": subsystems,
+            })),
+        )
+            .into_response();
+    }
+
+    let rows = subsystems
+        .iter()
+        .map(|(name, s)| {
+            let (state, color) = match s.state {
+                supervisor::State::Running => ("running", Some(Bit4Color::GREEN)),
+                supervisor::State::Restarting => ("restarting", Some(Bit4Color::YELLOW)),
+                supervisor::State::Failed => ("failed", Some(Bit4Color::RED)),
+                supervisor::State::Stopped => ("stopped", Some(Bit4Color::BRIGHT_BLACK)),
+            };
+            vec![
+                (name.to_string(), None),
+                (state.into(), color),
+                (if s.critical { "yes" } else { "no" }.into(), None),
+                (s.restarts.to_string(), None),
+                (s.last_error.clone().unwrap_or_default(), None),
+            ]
+        })
+        .collect();
+
+    let config = state.config.load();
+    let page = Page::from_iter(
+        "/health/live",
+        &config,
+        [
+            Theme::default().title_underlined("Liveness"),
+            status_line(status).into(),
+            table(
+                &["subsystem", "state", "critical", "restarts", "last error"],
+                rows,
+            )
+            .into(),
+        ],
+    );
+
+    (code, page.render(ctx.render)).into_response()
+}
+
+/// 503 when a critical dependency is down or stale (`health.critical`), `degraded` if only others are
+pub async fn ready(
+    State(state): State<WebServerState>,
+    Extension(ctx): Extension<RequestContext>,
+) -> Response {
+    let config = state.config.load();
+    let report = state
+        .readiness
+        .check(&config, &state.mm, state.lastfm.as_deref(), &state.health)
+        .await;
+
+    let (status, code) = match report.status {
+        ReadyState::Ready => ("ready", StatusCode::OK),
+        ReadyState::Degraded => ("degraded", StatusCode::OK),
+        ReadyState::NotReady => ("not_ready", StatusCode::SERVICE_UNAVAILABLE),
+    };
+
+    if ctx.render.format == RenderFormat::Json {
+        return (code, Json(report)).into_response();
+    }
+
+    let rows = report
+        .checks
+        .iter()
+        .map(|(dependency, check)| {
+            let (state, color) = match check.state {
+                CheckState::Ok => ("ok", Some(Bit4Color::GREEN)),
+                CheckState::Stale => ("stale", Some(Bit4Color::YELLOW)),
+                CheckState::Down => ("down", Some(Bit4Color::RED)),
+                CheckState::Disabled => ("disabled", Some(Bit4Color::BRIGHT_BLACK)),
+            };
+            vec![
+                (dependency.to_string(), None),
+                (state.into(), color),
+                (if check.critical { "yes" } else { "no" }.into(), None),
+                (check.age_secs.map(age).unwrap_or_default(), None),
+                (check.detail.clone(), None),
+            ]
+        })
+        .collect();
+
+    let page = Page::from_iter(
+        "/health/ready",
+        &config,
+        [
+            Theme::default().title_underlined("Readiness"),
+            status_line(status).into(),
+            table(&["dependency", "state", "critical", "age", "detail"], rows).into(),
+        ],
+    );
+
+    (code, page.render(ctx.render)).into_response()
+}
+
+fn status_line(status: &str) -> Vec<Object> {
+    let color = match status {
+        "ok" | "ready" => Bit4Color::GREEN,
+        "degraded" => Bit4Color::YELLOW,
+        _ => Bit4Color::RED,
+    };
+
+    Theme::default().label(
+        "status",
+        vec![
+            TextBlobBuilder::new(status)
+                .style(Style::new().fg(color).bold(true))
+                .into(),
+            TextBlobBuilder::new("\n\n").into(),
+        ],
+    )
+}
+
+/// Left aligned columns, cells without a color use the terminal default
+fn table(header: &[&str], rows: Vec<Vec<(String, Option<Bit4Color>)>>) -> CodeBlockBuilder {
+    let widths = header
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|(cell, _)| cell.width())
+                .fold(title.width(), usize::max)
+        })
+        .collect::<Vec<_>>();
+    // two spaces between columns, the last one isn't padded
+    let pad = |text: &str, i: usize| match widths.get(i) {
+        Some(width) if i + 1 < widths.len() => {
+            format!(
+                "{text}{}",
+                " ".repeat(width.saturating_sub(text.width()) + 2)
+            )
+        }
+        _ => text.to_string(),
+    };
+
+    let theme = Theme::default();
+    let mut objects: Vec<Object> = header
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            TextBlobBuilder::new(pad(title, i))
+                .style(theme.label.clone())
+                .into()
+        })
+        .collect();
+    objects.push(TextBlobBuilder::new("\n").into());
+
+    for row in rows {
+        for (i, (cell, color)) in row.into_iter().enumerate() {
+            let style = color.map(|c| Style::new().fg(c)).unwrap_or_default();
+            objects.push(TextBlobBuilder::new(pad(&cell, i)).style(style).into());
+        }
+        objects.push(TextBlobBuilder::new("\n").into());
+    }
+
+    CodeBlockBuilder::new(objects)
+}
+
+/// `42s`, `5m`, `3h` or `12d`
+fn age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
@@ -0,0 +1,73 @@
+use std::{io, path::Path as FsPath};
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+};
+use reqwest::StatusCode;
+
+use crate::{honeypot, webserver::WebServerState};
+
+/// Recorded SSH sessions, newest first, with the MaxMind data of their source IP
+pub async fn sessions(State(state): State<WebServerState>) -> impl IntoResponse {
+    let dir = state.config.load().privacy.honeypot.dir.clone();
+    let mut sessions = match honeypot::list(FsPath::new(&dir)).await {
+        Ok(sessions) => sessions,
+        // nothing was recorded yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    for session in &mut sessions {
+        session.maxmind = session.ip.and_then(|ip| state.mm.lookup(ip).ok());
+    }
+
+    (StatusCode::OK, Json(sessions)).into_response()
+}
+
+/// The session's events as JSON lines
+pub async fn events(
+    State(state): State<WebServerState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    download(&state, &id, "jsonl", "application/x-ndjson").await
+}
+
+/// The session as an asciinema v2 cast, `asciinema play <file>` replays it
+pub async fn cast(
+    State(state): State<WebServerState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    download(&state, &id, "cast", "application/x-asciicast").await
+}
+
+async fn download(
+    state: &WebServerState,
+    id: &str,
+    extension: &str,
+    content_type: &'static str,
+) -> axum::response::Response {
+    let dir = state.config.load().privacy.honeypot.dir.clone();
+    let Some(path) = honeypot::path(FsPath::new(&dir), id, extension) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match tokio::fs::read(&path).await {
+        Ok(data) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, content_type.to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{id}.{extension}\""),
+                ),
+            ],
+            data,
+        )
+            .into_response(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
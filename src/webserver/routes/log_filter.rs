@@ -0,0 +1,34 @@
+use axum::{extract::State, response::IntoResponse};
+use reqwest::StatusCode;
+use tracing::info;
+
+use crate::webserver::WebServerState;
+
+pub async fn filter() -> impl IntoResponse {
+    match crate::logger::filter() {
+        Some(filter) => (StatusCode::OK, filter).into_response(),
+        None => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+    }
+}
+
+/// The body is the new filter in the RUST_LOG syntax, e.g. `info,kybe_backend::ssh=trace`
+pub async fn set_filter(body: String) -> impl IntoResponse {
+    match crate::logger::set_filter(body.trim()) {
+        Ok(filter) => {
+            info!("log filter changed to {filter}");
+            (StatusCode::OK, filter).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Goes back to the filter from the config
+pub async fn reset_filter(State(state): State<WebServerState>) -> impl IntoResponse {
+    match crate::logger::reset_filter(&state.config.load().logger) {
+        Ok(filter) => {
+            info!("log filter reset to {filter}");
+            (StatusCode::OK, filter).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -1,9 +1,10 @@
 pub mod canvas;
+pub mod content_page;
 pub mod fallback_404;
+pub mod health;
+pub mod honeypot;
 pub mod ip;
+pub mod log_filter;
 pub mod metrics;
-pub mod nix;
 pub mod now_playing;
 pub mod pgp;
-pub mod portfolio;
-pub mod root;
@@ -1,4 +1,4 @@
-use axum::{Extension, extract::State, http::header, response::IntoResponse};
+use axum::{Extension, extract::State, response::IntoResponse};
 use reqwest::StatusCode;
 
 use crate::webserver::{
@@ -46,14 +46,8 @@ pub async fn pgp(
     ];
     page.append(&mut common::footer::footer());
 
-    let page = Page::from_iter("/pgp", &state.config, page);
+    let config = state.config.load();
+    let page = Page::from_iter("/pgp", &config, page);
 
-    let mut result = page.render(&ctx.user_agent);
-
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, result.take_content_type())],
-        result.take_data(),
-    )
-        .into_response()
+    (StatusCode::OK, page.render(ctx.render)).into_response()
 }